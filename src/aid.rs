@@ -0,0 +1,128 @@
+#[cfg(feature = "serialport")]
+use crate::send_ubx_command;
+use crate::{Position, build_ubx_frame, msl_to_ellipsoid_height, utc_fields};
+#[cfg(feature = "serialport")]
+use serialport::SerialPort;
+#[cfg(feature = "serialport")]
+use std::io;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const UBX_CLASS_AID: u8 = 0x0B;
+pub const UBX_ID_AID_INI: u8 = 0x01;
+
+// flags of AID-INI
+const AID_INI_POS_VALID: u32 = 1 << 0;
+const AID_INI_TIME_VALID: u32 = 1 << 1;
+const AID_INI_CLOCK_D_VALID: u32 = 1 << 2;
+const AID_INI_LLA: u32 = 1 << 5;
+const AID_INI_ALT_INV: u32 = 1 << 6;
+const AID_INI_UTC: u32 = 1 << 10;
+
+/*
+    UBX-AID-INI (0x0B 0x01), initial position / time / clock drift for a fast start.
+
+    A receiver starting cold searches the whole sky, knowing roughly where and when it is
+    (a few km, a few seconds) cuts TTFF considerably. Send right after power up:
+
+    AidIniBuilder::new()
+        .position(&last_known, 5_000.0)
+        .time(SystemTime::now(), Duration::from_secs(2))
+        .send(&mut *port)?;
+
+    Position goes out as lat / lon / ellipsoid height (lla flag), the altitude is marked
+    invalid when height_msl is NaN. Time goes out in the UTC date / time format (utc
+    flag), so the host's clock is used as is without knowing the leap seconds. Anything
+    not set is flagged invalid and ignored by the receiver. AID-INI is not acknowledged.
+*/
+#[derive(Debug, Clone)]
+pub struct AidIniBuilder {
+    payload: [u8; 48],
+    flags: u32,
+}
+
+impl Default for AidIniBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AidIniBuilder {
+    pub fn new() -> Self {
+        AidIniBuilder {
+            payload: [0; 48],
+            flags: 0,
+        }
+    }
+
+    fn set(mut self, offset: usize, bytes: &[u8]) -> Self {
+        self.payload[offset..offset + bytes.len()].copy_from_slice(bytes);
+        self
+    }
+
+    // Approximate position, accuracy in meters
+    pub fn position(mut self, pos: &Position, accuracy: f64) -> Self {
+        self.flags |= AID_INI_POS_VALID | AID_INI_LLA;
+        let alt_cm = if pos.height_msl.is_finite() {
+            self.flags &= !AID_INI_ALT_INV;
+            (msl_to_ellipsoid_height(pos.lat, pos.lon, pos.height_msl) * 100.0).round() as i32
+        } else {
+            self.flags |= AID_INI_ALT_INV;
+            0
+        };
+        let acc_cm = (accuracy * 100.0).round().clamp(0.0, u32::MAX as f64) as u32;
+
+        self.set(0, &((pos.lat * 1e7).round() as i32).to_le_bytes())
+            .set(4, &((pos.lon * 1e7).round() as i32).to_le_bytes())
+            .set(8, &alt_cm.to_le_bytes())
+            .set(12, &acc_cm.to_le_bytes())
+    }
+
+    /*
+        Current UTC time and how far off it may be.
+
+        wnoOrDate becomes YYMM (year since 2000, month) and towOrTime DDHHMMSS, both
+        decimal, the fraction of the second goes to towNs.
+    */
+    pub fn time(mut self, utc: SystemTime, accuracy: Duration) -> Self {
+        self.flags |= AID_INI_TIME_VALID | AID_INI_UTC;
+        let (year, month, day, hour, min, sec, _) = utc_fields(utc);
+        let nanos = utc
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0);
+
+        let date = (year - 2000).clamp(0, 99) as u16 * 100 + month as u16;
+        let time = day * 1_000_000 + hour * 10_000 + min * 100 + sec;
+        let acc_ms = accuracy.as_millis().min(u32::MAX as u128) as u32;
+        let acc_ns = accuracy.subsec_nanos() % 1_000_000;
+
+        self.set(18, &date.to_le_bytes())
+            .set(20, &time.to_le_bytes())
+            .set(24, &(nanos as i32).to_le_bytes())
+            .set(28, &acc_ms.to_le_bytes())
+            .set(32, &acc_ns.to_le_bytes())
+    }
+
+    // Receiver clock drift (e.g. NavClock::clk_d from the last session) and its
+    // accuracy, ns/s
+    pub fn clock_drift(mut self, drift: i32, accuracy: u32) -> Self {
+        self.flags |= AID_INI_CLOCK_D_VALID;
+        self.set(36, &drift.to_le_bytes())
+            .set(40, &accuracy.to_le_bytes())
+    }
+
+    pub fn payload(&self) -> [u8; 48] {
+        let mut payload = self.payload;
+        payload[44..48].copy_from_slice(&self.flags.to_le_bytes());
+        payload
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        build_ubx_frame(UBX_CLASS_AID, UBX_ID_AID_INI, &self.payload())
+    }
+
+    #[cfg(feature = "serialport")]
+    pub fn send(&self, port: &mut dyn SerialPort) -> io::Result<()> {
+        send_ubx_command(port, UBX_CLASS_AID, UBX_ID_AID_INI, &self.payload()).map(|_| ())
+    }
+}
@@ -0,0 +1,171 @@
+use crate::{UbxHeader, UbxMessage, build_ubx_frame, ubx_checksum};
+use std::io::{self, Read, Write};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// File header: magic followed by the format version
+pub const BINLOG_MAGIC: [u8; 7] = *b"UBX7LOG";
+pub const BINLOG_VERSION: u8 = 1;
+// Longest record: time stamp and a frame with the largest payload a UBX length allows
+const BINLOG_MAX_RECORD_LEN: usize = 8 + 6 + 65_535 + 2;
+
+/*
+    Compact binary log of received UBX messages.
+
+    After the 8 byte header every record is
+        u32 LE  record length (bytes following this field)
+        u64 LE  receive time, microseconds since the UNIX epoch
+        ...     the complete UBX frame, sync to checksum
+    The record length lets a reader of a later version skip fields it does not know.
+
+    Frames are stored as received, so a log can be replayed into a UbxParser unchanged.
+*/
+pub struct BinLogWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> BinLogWriter<W> {
+    // Writes the file header
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(&BINLOG_MAGIC)?;
+        writer.write_all(&[BINLOG_VERSION])?;
+        Ok(BinLogWriter { writer })
+    }
+
+    // Logs msg stamped with the current time
+    pub fn write_message(&mut self, msg: &UbxMessage) -> io::Result<()> {
+        self.write_message_at(SystemTime::now(), msg)
+    }
+
+    pub fn write_message_at(&mut self, time: SystemTime, msg: &UbxMessage) -> io::Result<()> {
+        let frame = build_ubx_frame(msg.class, msg.id, &msg.payload);
+        let micros = time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_micros() as u64;
+
+        self.writer
+            .write_all(&((8 + frame.len()) as u32).to_le_bytes())?;
+        self.writer.write_all(&micros.to_le_bytes())?;
+        self.writer.write_all(&frame)
+    }
+
+    // Flushes and hands the writer back
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/*
+    Reads a BinLogWriter log back as (receive time, message).
+
+    Records whose frame fails the checksum are skipped. Iteration ends at the end of the
+    log, a truncated last record (logger killed mid write) ends it too. A record length
+    no frame can have means the log is corrupt from there on, iteration stops with an
+    InvalidData error. Any other I/O error ends it early as well, take_error() then
+    returns the error.
+*/
+pub struct BinLogReader<R: Read> {
+    reader: R,
+    error: Option<io::Error>,
+}
+
+impl<R: Read> BinLogReader<R> {
+    // Checks the file header, fails with InvalidData on a foreign file or newer version
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header)?;
+        if header[..7] != BINLOG_MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a UBX binary log",
+            ));
+        }
+        if header[7] != BINLOG_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported UBX binary log version {}", header[7]),
+            ));
+        }
+
+        Ok(BinLogReader {
+            reader,
+            error: None,
+        })
+    }
+
+    // The I/O error that ended iteration, if any
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    // Next record, Ok(None) at the end of the log
+    fn read_record(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut len = [0u8; 4];
+        match self.reader.read_exact(&mut len) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+
+        let len = u32::from_le_bytes(len) as usize;
+        if len > BINLOG_MAX_RECORD_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("UBX binary log record of {} bytes", len),
+            ));
+        }
+
+        let mut record = vec![0u8; len];
+        match self.reader.read_exact(&mut record) {
+            Ok(()) => Ok(Some(record)),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+// Time and message of one record, None if it does not hold an intact frame
+fn decode_record(record: &[u8]) -> Option<(SystemTime, UbxMessage)> {
+    let micros = u64::from_le_bytes(record.get(..8)?.try_into().ok()?);
+    let frame = &record[8..];
+    let header = UbxHeader::parse(frame)?;
+    let frame = frame.get(..header.frame_len())?;
+
+    let (ck_a, ck_b) = ubx_checksum(&frame[2..frame.len() - 2]);
+    if frame[frame.len() - 2..] != [ck_a, ck_b] {
+        return None;
+    }
+
+    let msg = UbxMessage {
+        class: header.class,
+        id: header.id,
+        payload: frame[6..frame.len() - 2].to_vec(),
+    };
+    Some((UNIX_EPOCH + Duration::from_micros(micros), msg))
+}
+
+impl<R: Read> Iterator for BinLogReader<R> {
+    type Item = (SystemTime, UbxMessage);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.read_record() {
+                Ok(Some(record)) => {
+                    if let Some(entry) = decode_record(&record) {
+                        return Some(entry);
+                    }
+                }
+                Ok(None) => return None,
+                Err(e) => {
+                    self.error = Some(e);
+                    return None;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,1156 @@
+#[cfg(feature = "serialport")]
+use crate::{
+    DEFAULT_POLL_TIMEOUT, InfLevels, PollConfig, UbxError, UbxParser, flush_input, poll_message,
+    send_ubx_command, wait_for_ack_of,
+};
+use crate::{GnssId, build_ubx_frame};
+#[cfg(feature = "serialport")]
+use serialport::SerialPort;
+#[cfg(feature = "serialport")]
+use std::io;
+use std::time::Duration;
+
+pub const UBX_CLASS_CFG: u8 = 0x06;
+
+/*
+    Generic UBX-CFG frame builder, for configuration messages without a dedicated helper.
+
+    Fields are set by their byte offset in the payload as listed in the protocol spec,
+    the payload grows with zeros as needed. Multi byte fields are little endian.
+
+    let frame = CfgBuilder::new(0x24) // CFG-NAV5
+        .mask(0x0001)                 // apply dynamic model only
+        .set_u8(2, 4)                 // automotive
+        .set_u8(35, 0)                // pad to the 36 byte payload
+        .build();
+*/
+#[derive(Debug, Clone)]
+pub struct CfgBuilder {
+    id: u8,
+    payload: Vec<u8>,
+}
+
+impl CfgBuilder {
+    pub fn new(id: u8) -> Self {
+        CfgBuilder {
+            id,
+            payload: Vec::new(),
+        }
+    }
+
+    fn set_bytes(mut self, offset: usize, bytes: &[u8]) -> Self {
+        if self.payload.len() < offset + bytes.len() {
+            self.payload.resize(offset + bytes.len(), 0);
+        }
+        self.payload[offset..offset + bytes.len()].copy_from_slice(bytes);
+        self
+    }
+
+    pub fn set_u8(self, offset: usize, val: u8) -> Self {
+        self.set_bytes(offset, &[val])
+    }
+
+    pub fn set_u16_le(self, offset: usize, val: u16) -> Self {
+        self.set_bytes(offset, &val.to_le_bytes())
+    }
+
+    pub fn set_u32_le(self, offset: usize, val: u32) -> Self {
+        self.set_bytes(offset, &val.to_le_bytes())
+    }
+
+    // R4
+    pub fn set_f32_le(self, offset: usize, val: f32) -> Self {
+        self.set_bytes(offset, &val.to_le_bytes())
+    }
+
+    // R8
+    pub fn set_f64_le(self, offset: usize, val: f64) -> Self {
+        self.set_bytes(offset, &val.to_le_bytes())
+    }
+
+    // ORs bits into the U2 parameter mask at offset 0 (CFG-NAV5 and friends)
+    pub fn mask(self, bits: u16) -> Self {
+        self.mask_at(0, bits)
+    }
+
+    // ORs bits into a U2 mask kept elsewhere, e.g. mask1 of CFG-NAVX5 at offset 2
+    pub fn mask_at(self, offset: usize, bits: u16) -> Self {
+        let current = match self.payload.get(offset..offset + 2) {
+            Some(mask) => u16::from_le_bytes([mask[0], mask[1]]),
+            None => 0,
+        };
+        self.set_u16_le(offset, current | bits)
+    }
+
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        build_ubx_frame(UBX_CLASS_CFG, self.id, &self.payload)
+    }
+
+    #[cfg(feature = "serialport")]
+    pub fn send(&self, port: &mut dyn SerialPort) -> io::Result<()> {
+        send_ubx_command(port, UBX_CLASS_CFG, self.id, &self.payload).map(|_| ())
+    }
+}
+
+// Which battery backed navigation data is cleared by reset_receiver
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResetMode {
+    // keep everything
+    Hot,
+    // clear ephemeris
+    Warm,
+    // clear everything (ephemeris, almanac, position, time ...)
+    Cold,
+}
+
+// resetMode of CFG-RST
+#[cfg(feature = "serialport")]
+const RESET_MODE_SOFTWARE: u8 = 0x01;
+#[cfg(feature = "serialport")]
+const RESET_MODE_GNSS_ONLY: u8 = 0x02;
+
+// UBX-CFG-RST (0x06 0x04), not acknowledged by the receiver
+#[cfg(feature = "serialport")]
+fn send_reset(port: &mut dyn SerialPort, mode: ResetMode, reset_mode: u8) -> io::Result<()> {
+    let nav_bbr_mask: u16 = match mode {
+        ResetMode::Hot => 0x0000,
+        ResetMode::Warm => 0x0001,
+        ResetMode::Cold => 0xFFFF,
+    };
+    let mask = nav_bbr_mask.to_le_bytes();
+    send_ubx_command(
+        port,
+        UBX_CLASS_CFG,
+        0x04,
+        &[mask[0], mask[1], reset_mode, 0x00],
+    )
+    .map(|_| ())
+}
+
+/*
+    UBX-CFG-RST (0x06 0x04).
+
+    Only the GNSS part is restarted (resetMode 0x02), the USB / serial connection stays up.
+    The receiver does not acknowledge CFG-RST.
+*/
+#[cfg(feature = "serialport")]
+pub fn reset_receiver(port: &mut dyn SerialPort, mode: ResetMode) -> io::Result<()> {
+    send_reset(port, mode, RESET_MODE_GNSS_ONLY)
+}
+
+// Standard NMEA sentences, class 0xF0
+pub const NMEA_STANDARD_MESSAGES: [(u8, u8); 6] = [
+    (0xF0, 0x00), // GGA
+    (0xF0, 0x01), // GLL
+    (0xF0, 0x02), // GSA
+    (0xF0, 0x03), // GSV
+    (0xF0, 0x04), // RMC
+    (0xF0, 0x05), // VTG
+];
+
+// UBX messages the receiver can output periodically
+pub const UBX_PERIODIC_MESSAGES: [(u8, u8); 13] = [
+    (0x01, 0x02), // NAV-POSLLH
+    (0x01, 0x03), // NAV-STATUS
+    (0x01, 0x04), // NAV-DOP
+    (0x01, 0x06), // NAV-SOL
+    (0x01, 0x07), // NAV-PVT
+    (0x01, 0x12), // NAV-VELNED
+    (0x01, 0x20), // NAV-TIMEGPS
+    (0x01, 0x21), // NAV-TIMEUTC
+    (0x01, 0x22), // NAV-CLOCK
+    (0x01, 0x30), // NAV-SVINFO
+    (0x01, 0x35), // NAV-SAT
+    (0x01, 0x60), // NAV-AOPSTATUS
+    (0x0A, 0x09), // MON-HW
+];
+
+/*
+    UBX-CFG-INF (0x06 0x02), which INF message levels a protocol outputs (0 UBX, 1 NMEA,
+    where the NMEA ones are the $GPTXT sentences). Applied to every port, e.g.
+    configure_inf(port, 1, InfLevels::NONE) silences $GPTXT.
+*/
+#[cfg(feature = "serialport")]
+pub fn configure_inf(
+    port: &mut dyn SerialPort,
+    protocol_id: u8,
+    levels: InfLevels,
+) -> io::Result<()> {
+    let mut inf = CfgBuilder::new(0x02).set_u8(0, protocol_id);
+    // infMsgMask per port: DDC, UART1, UART2, USB, SPI, reserved
+    for offset in 4..10 {
+        inf = inf.set_u8(offset, levels.mask());
+    }
+    inf.send(port)
+}
+
+// UBX-CFG-MSG (0x06 0x01), output rate of a message on the current port, in navigation
+// solutions (0 disables it, 1 every solution, 5 every fifth ...)
+#[cfg(feature = "serialport")]
+pub fn set_message_rate(port: &mut dyn SerialPort, class: u8, id: u8, rate: u8) -> io::Result<()> {
+    send_ubx_command(port, UBX_CLASS_CFG, 0x01, &[class, id, rate]).map(|_| ())
+}
+
+// Stops GGA, GLL, GSA, GSV, RMC and VTG on the current port so polls are not buried
+#[cfg(feature = "serialport")]
+pub fn disable_all_nmea(port: &mut dyn SerialPort) -> io::Result<()> {
+    for (class, id) in NMEA_STANDARD_MESSAGES {
+        set_message_rate(port, class, id, 0)?;
+    }
+    Ok(())
+}
+
+// Stops the periodic UBX output listed in UBX_PERIODIC_MESSAGES on the current port
+#[cfg(feature = "serialport")]
+pub fn disable_all_periodic_ubx(port: &mut dyn SerialPort) -> io::Result<()> {
+    for (class, id) in UBX_PERIODIC_MESSAGES {
+        set_message_rate(port, class, id, 0)?;
+    }
+    Ok(())
+}
+
+// NAV messages enabled by enable_debug_messages
+pub const DEBUG_NAV_MESSAGES: [(u8, u8); 5] = [
+    (0x01, 0x02), // NAV-POSLLH
+    (0x01, 0x03), // NAV-STATUS
+    (0x01, 0x35), // NAV-SAT
+    (0x01, 0x04), // NAV-DOP
+    (0x01, 0x12), // NAV-VELNED
+];
+
+// Turns on the common NAV messages at rate on the current port, for bring-up of a new
+// module. disable_all_periodic_ubx undoes it.
+#[cfg(feature = "serialport")]
+pub fn enable_debug_messages(port: &mut dyn SerialPort, rate: u8) -> io::Result<()> {
+    for (class, id) in DEBUG_NAV_MESSAGES {
+        set_message_rate(port, class, id, rate)?;
+    }
+    Ok(())
+}
+
+/*
+    UBX-CFG-ANT (0x06 0x13), active antenna supply and supervisor.
+
+    enable_supply: switch the antenna supply with the receiver (svcs)
+    short_detect: detect a shorted antenna, the supply is then powered down (scd, pdwnOnSCD)
+    open_detect: detect a disconnected antenna (ocd)
+    auto_recovery: retry the supply after a short circuit (recovery)
+
+    The pin assignment is left unchanged (reconfig bit clear). MonHw::antenna_status()
+    reports what the supervisor sees.
+*/
+#[cfg(feature = "serialport")]
+pub fn configure_antenna(
+    port: &mut dyn SerialPort,
+    enable_supply: bool,
+    short_detect: bool,
+    open_detect: bool,
+    auto_recovery: bool,
+) -> io::Result<()> {
+    let flags = u16::from(enable_supply)
+        | u16::from(short_detect) << 1
+        | u16::from(open_detect) << 2
+        | u16::from(short_detect) << 3
+        | u16::from(auto_recovery) << 4;
+
+    CfgBuilder::new(0x13)
+        .set_u16_le(0, flags)
+        .set_u16_le(2, 0) // pins
+        .send(port)
+}
+
+/*
+    UBX-CFG-NAVX5 (0x06 0x23) extended navigation settings, only the fields given are
+    changed (through mask1), the others keep their current value.
+
+    min_cno: dBHz a satellite needs to be used in the solution, raising it rejects weak
+    (often multipath) signals
+    min_svs: satellites required for a fix
+    enable_aop: AssistNow Autonomous
+
+    min / max satellites share one mask bit, so setting min_svs first polls the current
+    maxSVs and writes it back unchanged.
+*/
+#[cfg(feature = "serialport")]
+pub fn configure_navx5(
+    port: &mut dyn SerialPort,
+    min_cno: Option<u8>,
+    min_svs: Option<u8>,
+    enable_aop: Option<bool>,
+) -> io::Result<()> {
+    let mut navx5 = CfgBuilder::new(0x23)
+        .set_u16_le(0, 0) // version
+        .set_u8(39, 0); // 40 byte payload
+
+    if let Some(min_svs) = min_svs {
+        let config = PollConfig {
+            retries: 3,
+            timeout: DEFAULT_POLL_TIMEOUT,
+        };
+        let current = poll_message(port, UBX_CLASS_CFG, 0x23, &[], &config)?;
+        let max_svs = *current.payload.get(11).ok_or(UbxError::InvalidFrame)?;
+        navx5 = navx5
+            .mask_at(2, 1 << 2) // minMax
+            .set_u8(10, min_svs)
+            .set_u8(11, max_svs);
+    }
+    if let Some(min_cno) = min_cno {
+        navx5 = navx5.set_u8(12, min_cno).mask_at(2, 1 << 3);
+    }
+    if let Some(enable_aop) = enable_aop {
+        navx5 = navx5.set_u8(27, u8::from(enable_aop)).mask_at(2, 1 << 14);
+    }
+
+    navx5.send(port)
+}
+
+/*
+    UBX-CFG-TP5 (0x06 0x31) time pulse parameters, expressed as period / pulse length.
+
+    antenna_cable_delay_ns compensates the signal delay of the antenna cable (about 5 ns
+    per meter of RG-58), rf_group_delay_ns the delay through the RF front end / filters.
+    user_delay_ns shifts the pulse on top of both.
+*/
+#[derive(Debug, Clone, Copy)]
+pub struct TimepulseConfig {
+    // 0 = TIMEPULSE, 1 = TIMEPULSE2
+    pub tp_idx: u8,
+    pub antenna_cable_delay_ns: i16,
+    pub rf_group_delay_ns: i16,
+    // period / pulse length while no fix is available, us
+    pub period_us: u32,
+    pub pulse_len_us: u32,
+    // period / pulse length once locked to GPS time, us
+    pub period_locked_us: u32,
+    pub pulse_len_locked_us: u32,
+    pub user_delay_ns: i32,
+    pub active: bool,
+    // use the locked values once GPS time is valid
+    pub lock_gps: bool,
+    // align the pulse to the top of second
+    pub align_to_tow: bool,
+    // true = rising edge at top of second
+    pub rising_edge: bool,
+}
+
+impl Default for TimepulseConfig {
+    // 1 Hz, 100 ms pulse, receiver default 50 ns cable delay
+    fn default() -> Self {
+        TimepulseConfig {
+            tp_idx: 0,
+            antenna_cable_delay_ns: 50,
+            rf_group_delay_ns: 0,
+            period_us: 1_000_000,
+            pulse_len_us: 100_000,
+            period_locked_us: 1_000_000,
+            pulse_len_locked_us: 100_000,
+            user_delay_ns: 0,
+            active: true,
+            lock_gps: true,
+            align_to_tow: true,
+            rising_edge: true,
+        }
+    }
+}
+
+#[cfg(feature = "serialport")]
+pub fn configure_timepulse(port: &mut dyn SerialPort, config: &TimepulseConfig) -> io::Result<()> {
+    let flags = u32::from(config.active)
+        | u32::from(config.lock_gps) << 1
+        | u32::from(config.lock_gps) << 2 // lockedOtherSet
+        | 1 << 4 // isLength, pulse length in us rather than a duty cycle
+        | u32::from(config.align_to_tow) << 5
+        | u32::from(config.rising_edge) << 6;
+
+    CfgBuilder::new(0x31)
+        .set_u8(0, config.tp_idx)
+        .set_u16_le(4, config.antenna_cable_delay_ns as u16)
+        .set_u16_le(6, config.rf_group_delay_ns as u16)
+        .set_u32_le(8, config.period_us)
+        .set_u32_le(12, config.period_locked_us)
+        .set_u32_le(16, config.pulse_len_us)
+        .set_u32_le(20, config.pulse_len_locked_us)
+        .set_u32_le(24, config.user_delay_ns as u32)
+        .set_u32_le(28, flags)
+        .send(port)
+}
+
+// UBX-CFG-RATE (0x06 0x08), interval between measurements in ms (200 = 5 Hz). One
+// navigation solution per measurement, aligned to GPS time.
+#[cfg(feature = "serialport")]
+pub fn set_measurement_rate(port: &mut dyn SerialPort, meas_rate_ms: u16) -> io::Result<()> {
+    measurement_rate(meas_rate_ms).send(port)
+}
+
+#[cfg(feature = "serialport")]
+fn measurement_rate(meas_rate_ms: u16) -> CfgBuilder {
+    CfgBuilder::new(0x08)
+        .set_u16_le(0, meas_rate_ms)
+        .set_u16_le(2, 1) // navRate
+        .set_u16_le(4, 1) // timeRef, GPS time
+}
+
+ubx_message! {
+    // UBX-CFG-RATE (0x06 0x08) as reported by a poll
+    pub struct CfgRate(0x06, 0x08) {
+        // ms between measurements
+        pub meas_rate: u16 = u16 @ 0,
+        // measurements per navigation solution
+        pub nav_rate: u16 = u16 @ 2,
+        // 0 UTC, 1 GPS time
+        pub time_ref: u16 = u16 @ 4,
+    }
+}
+
+impl CfgRate {
+    // Navigation solutions per second
+    pub fn solution_rate_hz(&self) -> f64 {
+        1000.0 / (self.meas_rate as f64 * self.nav_rate.max(1) as f64)
+    }
+}
+
+pub fn parse_cfg_rate(payload: &[u8]) -> Option<CfgRate> {
+    CfgRate::parse(payload)
+}
+
+// Polls CFG-RATE, e.g. to confirm set_measurement_rate
+#[cfg(feature = "serialport")]
+pub fn get_measurement_rate(port: &mut dyn SerialPort) -> Result<CfgRate, UbxError> {
+    let config = PollConfig {
+        retries: 3,
+        timeout: DEFAULT_POLL_TIMEOUT,
+    };
+    let msg = poll_message(port, UBX_CLASS_CFG, CfgRate::ID, &[], &config)?;
+    parse_cfg_rate(&msg.payload).ok_or(UbxError::InvalidFrame)
+}
+
+// Attempts of a CFG write that is NAKed or goes unanswered, and the pause between them
+pub const DEFAULT_CFG_RETRIES: u8 = 3;
+pub const DEFAULT_CFG_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+// Waits for the ACK-ACK / ACK-NAK of CFG message id (of any CFG message if None),
+// ACK-NAK becomes UbxError::Nak
+#[cfg(feature = "serialport")]
+fn wait_for_cfg_ack(port: &mut dyn SerialPort, id: Option<u8>) -> Result<(), UbxError> {
+    match wait_for_ack_of(port, UBX_CLASS_CFG, id, DEFAULT_POLL_TIMEOUT)? {
+        (_, true) => Ok(()),
+        (id, false) => Err(UbxError::Nak {
+            class: UBX_CLASS_CFG,
+            id,
+        }),
+    }
+}
+
+#[cfg(feature = "serialport")]
+fn retry_acked(
+    port: &mut dyn SerialPort,
+    id: Option<u8>,
+    send: &mut dyn FnMut(&mut dyn SerialPort) -> io::Result<()>,
+    retries: u8,
+    delay: Duration,
+) -> Result<(), UbxError> {
+    let mut last_error = UbxError::Timeout;
+    for attempt in 0..retries.max(1) {
+        if attempt > 0 {
+            std::thread::sleep(delay);
+        }
+        send(port)?;
+        match wait_for_cfg_ack(port, id) {
+            Ok(()) => return Ok(()),
+            Err(e @ (UbxError::Nak { .. } | UbxError::Timeout)) => last_error = e,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(last_error)
+}
+
+/*
+    Runs build_and_send and waits for the receiver to acknowledge, sending again (after
+    delay) on ACK-NAK or no answer, up to retries attempts in total. Busy receivers NAK
+    or drop the odd CFG write under buffer pressure.
+
+    build_and_send must write a single CFG message, any of the CFG helpers or a
+    CfgBuilder fits:
+
+    configure_with_retry(&mut *port, |p| set_measurement_rate(p, 200), 3, delay)?;
+
+    Fails with the last UbxError::Nak / UbxError::Timeout once every attempt was
+    rejected, or at once on a write error.
+*/
+#[cfg(feature = "serialport")]
+pub fn configure_with_retry<F>(
+    port: &mut dyn SerialPort,
+    mut build_and_send: F,
+    retries: u8,
+    delay: Duration,
+) -> Result<(), UbxError>
+where
+    F: FnMut(&mut dyn SerialPort) -> io::Result<()>,
+{
+    retry_acked(port, None, &mut build_and_send, retries, delay)
+}
+
+// dynModel of CFG-NAV5, the platform model the navigation filter assumes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DynModel {
+    Portable,
+    Stationary,
+    Pedestrian,
+    Automotive,
+    Sea,
+    Airborne1g,
+    Airborne2g,
+    Airborne4g,
+    Unknown(u8),
+}
+
+impl DynModel {
+    pub fn from_u8(model: u8) -> DynModel {
+        match model {
+            0 => DynModel::Portable,
+            2 => DynModel::Stationary,
+            3 => DynModel::Pedestrian,
+            4 => DynModel::Automotive,
+            5 => DynModel::Sea,
+            6 => DynModel::Airborne1g,
+            7 => DynModel::Airborne2g,
+            8 => DynModel::Airborne4g,
+            other => DynModel::Unknown(other),
+        }
+    }
+
+    pub fn to_u8(&self) -> u8 {
+        match self {
+            DynModel::Portable => 0,
+            DynModel::Stationary => 2,
+            DynModel::Pedestrian => 3,
+            DynModel::Automotive => 4,
+            DynModel::Sea => 5,
+            DynModel::Airborne1g => 6,
+            DynModel::Airborne2g => 7,
+            DynModel::Airborne4g => 8,
+            DynModel::Unknown(model) => *model,
+        }
+    }
+}
+
+ubx_message! {
+    // UBX-CFG-NAV5 (0x06 0x24) navigation engine settings as reported by a poll
+    pub struct CfgNav5(0x06, 0x24, 36) {
+        pub mask: u16 = u16 @ 0,
+        pub dyn_model: u8 = u8 @ 2,
+        // 1 2D only, 2 3D only, 3 auto 2D / 3D
+        pub fix_mode: u8 = u8 @ 3,
+        // altitude used for 2D fixes, m
+        pub fixed_alt: f64 = i32 @ 4 * 0.01,
+        // minimum elevation of satellites used, degrees
+        pub min_elev: i8 = i8 @ 12,
+        // position / time DOP masks
+        pub p_dop: f64 = u16 @ 14 * 0.1,
+        pub t_dop: f64 = u16 @ 16 * 0.1,
+        // position accuracy mask, m
+        pub p_acc: u16 = u16 @ 18,
+        // time accuracy mask, m
+        pub t_acc: u16 = u16 @ 20,
+        // static hold threshold, cm/s
+        pub static_hold_thresh: u8 = u8 @ 22,
+        // DGPS correction timeout, s
+        pub dgps_timeout: u8 = u8 @ 23,
+    }
+}
+
+impl CfgNav5 {
+    pub fn dynamic_model(&self) -> DynModel {
+        DynModel::from_u8(self.dyn_model)
+    }
+}
+
+pub fn parse_cfg_nav5(payload: &[u8]) -> Option<CfgNav5> {
+    CfgNav5::parse(payload)
+}
+
+// CFG-NAV5 with only the dynamic model applied, confirm with get_dynamic_model
+#[cfg(feature = "serialport")]
+pub fn set_dynamic_model(port: &mut dyn SerialPort, model: DynModel) -> io::Result<()> {
+    CfgBuilder::new(0x24)
+        .mask(0x0001)
+        .set_u8(2, model.to_u8())
+        .set_u8(35, 0) // 36 byte payload
+        .send(port)
+}
+
+// Polls CFG-NAV5 for the dynamic model in use
+#[cfg(feature = "serialport")]
+pub fn get_dynamic_model(port: &mut dyn SerialPort) -> Result<DynModel, UbxError> {
+    let config = PollConfig {
+        retries: 3,
+        timeout: DEFAULT_POLL_TIMEOUT,
+    };
+    let msg = poll_message(port, UBX_CLASS_CFG, CfgNav5::ID, &[], &config)?;
+    parse_cfg_nav5(&msg.payload)
+        .map(|nav5| nav5.dynamic_model())
+        .ok_or(UbxError::InvalidFrame)
+}
+
+// Sends a CFG message and waits for its acknowledgement, retried per DEFAULT_CFG_RETRIES
+#[cfg(feature = "serialport")]
+fn send_acked(port: &mut dyn SerialPort, id: u8, payload: &[u8]) -> Result<(), UbxError> {
+    retry_acked(
+        port,
+        Some(id),
+        &mut |port| send_ubx_command(port, UBX_CLASS_CFG, id, payload).map(|_| ()),
+        DEFAULT_CFG_RETRIES,
+        DEFAULT_CFG_RETRY_DELAY,
+    )
+}
+
+// Saves the current configuration (all sections) to battery backed RAM and flash with
+// UBX-CFG-CFG (0x06 0x09), so it survives a power cycle
+#[cfg(feature = "serialport")]
+pub fn save_config(port: &mut dyn SerialPort) -> Result<(), UbxError> {
+    let payload = CfgBuilder::new(0x09)
+        .set_u32_le(0, 0) // clearMask
+        .set_u32_le(4, 0xFFFF) // saveMask
+        .set_u32_le(8, 0) // loadMask
+        .set_u8(12, 0x07) // deviceMask: BBR, flash, EEPROM
+        .payload()
+        .to_vec();
+    send_acked(port, 0x09, &payload)
+}
+
+// portID field of CFG-PRT
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortId {
+    I2c = 0,
+    Uart1 = 1,
+    Usb = 3,
+    Spi = 4,
+}
+
+// Protocol bits of the CFG-PRT inProtoMask / outProtoMask
+pub const PROTO_UBX: u16 = 0x0001;
+pub const PROTO_NMEA: u16 = 0x0002;
+pub const PROTO_RTCM: u16 = 0x0004;
+
+// Protocols of one direction of a port, the CFG-PRT inProtoMask / outProtoMask
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProtoMask {
+    pub ubx: bool,
+    pub nmea: bool,
+    // input only on u-blox 7, RTCM 2 corrections
+    pub rtcm: bool,
+}
+
+impl ProtoMask {
+    pub const NONE: ProtoMask = ProtoMask {
+        ubx: false,
+        nmea: false,
+        rtcm: false,
+    };
+
+    pub const UBX: ProtoMask = ProtoMask {
+        ubx: true,
+        nmea: false,
+        rtcm: false,
+    };
+
+    pub const NMEA: ProtoMask = ProtoMask {
+        ubx: false,
+        nmea: true,
+        rtcm: false,
+    };
+
+    pub const UBX_NMEA: ProtoMask = ProtoMask {
+        ubx: true,
+        nmea: true,
+        rtcm: false,
+    };
+
+    pub fn mask(&self) -> u16 {
+        (if self.ubx { PROTO_UBX } else { 0 })
+            | (if self.nmea { PROTO_NMEA } else { 0 })
+            | (if self.rtcm { PROTO_RTCM } else { 0 })
+    }
+
+    pub fn from_mask(mask: u16) -> ProtoMask {
+        ProtoMask {
+            ubx: mask & PROTO_UBX != 0,
+            nmea: mask & PROTO_NMEA != 0,
+            rtcm: mask & PROTO_RTCM != 0,
+        }
+    }
+}
+
+impl From<u16> for ProtoMask {
+    fn from(mask: u16) -> Self {
+        ProtoMask::from_mask(mask)
+    }
+}
+
+impl From<ProtoMask> for u16 {
+    fn from(mask: ProtoMask) -> Self {
+        mask.mask()
+    }
+}
+
+/*
+    Sets the input and output protocols of one port, everything else of its CFG-PRT
+    (baud rate, mode, ...) is read back and kept. Each port is independent, e.g. UBX out
+    on USB and NMEA out on UART1 for a legacy display:
+
+    set_protocols(&mut *port, PortId::Usb, ProtoMask::UBX, ProtoMask::UBX)?;
+    set_protocols(&mut *port, PortId::Uart1, ProtoMask::UBX, ProtoMask::NMEA)?;
+
+    port is the connection the commands go over, not necessarily port_id. Removing UBX
+    from the input of the port in use locks the host out until the next reset (or
+    forever, once saved).
+*/
+#[cfg(feature = "serialport")]
+pub fn set_protocols(
+    port: &mut dyn SerialPort,
+    port_id: PortId,
+    in_mask: ProtoMask,
+    out_mask: ProtoMask,
+) -> Result<(), UbxError> {
+    let mut payload = poll_port_config(port, port_id)?;
+    payload[12..14].copy_from_slice(&in_mask.mask().to_le_bytes());
+    payload[14..16].copy_from_slice(&out_mask.mask().to_le_bytes());
+    send_acked(port, 0x00, &payload)
+}
+
+/*
+    Complete receiver setup applied in one go by apply_profile.
+
+    The default is UBX only on UART1 at 115200 baud, 5 Hz, NAV-PVT every solution, all
+    other periodic output off, saved to flash, no restart.
+
+    restart ends the apply with a controlled software reset, which reloads the whole
+    configuration from battery backed RAM / flash and so fully applies e.g. a baud change.
+    With preserve_navigation the configuration is always saved before that reset (or it
+    would be lost), and a Cold restart is downgraded to Warm so the almanac, last position
+    and time survive for a fast reacquisition.
+*/
+#[derive(Debug, Clone)]
+pub struct DeviceProfile {
+    pub port_id: PortId,
+    // UART only, ignored for the other ports
+    pub baud_rate: u32,
+    pub in_proto_mask: u16,
+    pub out_proto_mask: u16,
+    pub measurement_rate_ms: u16,
+    // (class, id, rate) enabled once all NMEA and periodic UBX output is off
+    pub messages: Vec<(u8, u8, u8)>,
+    // save the result with CFG-CFG
+    pub save: bool,
+    // software reset after applying
+    pub restart: Option<ResetMode>,
+    pub preserve_navigation: bool,
+}
+
+impl Default for DeviceProfile {
+    fn default() -> Self {
+        DeviceProfile {
+            port_id: PortId::Uart1,
+            baud_rate: 115_200,
+            in_proto_mask: PROTO_UBX,
+            out_proto_mask: PROTO_UBX,
+            measurement_rate_ms: 200,
+            messages: vec![(0x01, 0x07, 1)], // NAV-PVT
+            save: true,
+            restart: None,
+            preserve_navigation: false,
+        }
+    }
+}
+
+impl DeviceProfile {
+    // Reset apply_profile ends with, never clearing the almanac when preserving
+    pub fn restart_mode(&self) -> Option<ResetMode> {
+        self.restart.map(|mode| match mode {
+            ResetMode::Cold if self.preserve_navigation => ResetMode::Warm,
+            mode => mode,
+        })
+    }
+
+    // Whether apply_profile writes the configuration with CFG-CFG, always before a
+    // restart when preserving
+    pub fn saves_config(&self) -> bool {
+        self.save || (self.preserve_navigation && self.restart.is_some())
+    }
+}
+
+// Current UBX-CFG-PRT (0x06 0x00) payload of port_id, 20 bytes
+#[cfg(feature = "serialport")]
+fn poll_port_config(port: &mut dyn SerialPort, port_id: PortId) -> Result<Vec<u8>, UbxError> {
+    let config = PollConfig {
+        retries: 3,
+        timeout: DEFAULT_POLL_TIMEOUT,
+    };
+    let current = poll_message(port, UBX_CLASS_CFG, 0x00, &[port_id as u8], &config)?;
+    if current.payload.len() < 20 || current.payload[0] != port_id as u8 {
+        return Err(UbxError::InvalidFrame);
+    }
+    Ok(current.payload[..20].to_vec())
+}
+
+// current (as polled) with new protocol masks, and the baud rate for UART1. The mode
+// field is kept: for DDC it holds the slave address, for SPI the SPI mode.
+#[cfg(feature = "serialport")]
+fn port_config_payload(current: &[u8], baud_rate: u32, in_mask: u16, out_mask: u16) -> Vec<u8> {
+    let mut payload = current.to_vec();
+    if payload[0] == PortId::Uart1 as u8 {
+        payload[8..12].copy_from_slice(&baud_rate.to_le_bytes());
+    }
+    payload[12..14].copy_from_slice(&in_mask.to_le_bytes());
+    payload[14..16].copy_from_slice(&out_mask.to_le_bytes());
+    payload
+}
+
+/*
+    Applies a DeviceProfile, stopping at the first message the receiver does not accept.
+
+    Order: message rates (everything off, then the profile's messages), measurement rate,
+    port / protocol configuration, save, restart. The save strictly precedes the reset,
+    which is not acknowledged and takes the receiver about a second to come back from.
+    Every CFG write is checked for ACK-ACK and retried up to DEFAULT_CFG_RETRIES times,
+    after that a NAK returns UbxError::Nak and a missing answer UbxError::Timeout.
+
+    The port configuration is polled first and only the protocol masks (and the UART
+    baud rate) are changed, the rest of CFG-PRT, e.g. the DDC slave address, is kept.
+
+    The receiver answers a baud change at the new rate, so for UART1 the host side is
+    switched after CFG-PRT and the change is confirmed by polling CFG-PRT back at the new
+    rate instead. port must be the connection to profile.port_id.
+*/
+#[cfg(feature = "serialport")]
+pub fn apply_profile(port: &mut dyn SerialPort, profile: &DeviceProfile) -> Result<(), UbxError> {
+    for (class, id) in NMEA_STANDARD_MESSAGES
+        .iter()
+        .chain(UBX_PERIODIC_MESSAGES.iter())
+    {
+        send_acked(port, 0x01, &[*class, *id, 0])?;
+    }
+    for &(class, id, rate) in &profile.messages {
+        send_acked(port, 0x01, &[class, id, rate])?;
+    }
+
+    let rate = measurement_rate(profile.measurement_rate_ms);
+    send_acked(port, 0x08, rate.payload())?;
+
+    let current = poll_port_config(port, profile.port_id)?;
+    let prt = port_config_payload(
+        &current,
+        profile.baud_rate,
+        profile.in_proto_mask,
+        profile.out_proto_mask,
+    );
+    if profile.port_id == PortId::Uart1 {
+        send_ubx_command(port, UBX_CLASS_CFG, 0x00, &prt)?;
+        // let the frame leave the UART before the receiver switches rate
+        std::thread::sleep(Duration::from_millis(100));
+        port.set_baud_rate(profile.baud_rate)
+            .map_err(io::Error::from)?;
+        flush_input(port)?;
+
+        let config = PollConfig {
+            retries: 3,
+            timeout: DEFAULT_POLL_TIMEOUT,
+        };
+        poll_message(port, UBX_CLASS_CFG, 0x00, &[profile.port_id as u8], &config)?;
+    } else {
+        send_acked(port, 0x00, &prt)?;
+    }
+
+    if profile.saves_config() {
+        save_config(port)?;
+    }
+    if let Some(mode) = profile.restart_mode() {
+        send_reset(port, mode, RESET_MODE_SOFTWARE)?;
+    }
+    Ok(())
+}
+
+/*
+    Datum of UBX-CFG-DAT: reference ellipsoid and the 7 parameter (Helmert)
+    transformation between it and WGS84, positions are then output in this datum.
+
+    Shifts and rotations follow the u-blox datum table (NIMA TR8350.2 convention, local
+    datum to WGS84), most national datums only need the three shifts.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DatumParams {
+    // semi-major axis, m
+    pub major_axis: f64,
+    // inverse flattening 1/f
+    pub flattening: f64,
+    // shift, m
+    pub dx: f32,
+    pub dy: f32,
+    pub dz: f32,
+    // rotation, arc seconds
+    pub rot_x: f32,
+    pub rot_y: f32,
+    pub rot_z: f32,
+    // scale change, ppm
+    pub scale: f32,
+}
+
+impl DatumParams {
+    const fn shifted(major_axis: f64, flattening: f64, dx: f32, dy: f32, dz: f32) -> Self {
+        DatumParams {
+            major_axis,
+            flattening,
+            dx,
+            dy,
+            dz,
+            rot_x: 0.0,
+            rot_y: 0.0,
+            rot_z: 0.0,
+            scale: 0.0,
+        }
+    }
+
+    // Factory default
+    pub const WGS84: DatumParams = Self::shifted(6_378_137.0, 298.257223563, 0.0, 0.0, 0.0);
+    // European Datum 1950, mean solution, International 1924 ellipsoid
+    pub const ED50: DatumParams = Self::shifted(6_378_388.0, 297.0, -87.0, -98.0, -121.0);
+    // Ordnance Survey of Great Britain 1936, mean solution, Airy 1830 ellipsoid
+    pub const OSGB36: DatumParams = Self::shifted(6_377_563.396, 299.3249646, 375.0, -111.0, 431.0);
+    // North American Datum 1927, CONUS mean, Clarke 1866 ellipsoid
+    pub const NAD27_CONUS: DatumParams =
+        Self::shifted(6_378_206.4, 294.9786982, -8.0, 160.0, 176.0);
+    // Tokyo, mean solution, Bessel 1841 ellipsoid
+    pub const TOKYO: DatumParams = Self::shifted(6_377_397.155, 299.1528128, -148.0, 507.0, 685.0);
+}
+
+// UBX-CFG-DAT as reported by a poll
+#[derive(Debug, Clone)]
+pub struct CfgDat {
+    // 0 WGS84, -1 (0xFFFF) user defined, others are u-blox standard datums
+    pub datum_num: u16,
+    // e.g. "WGS84", "USER"
+    pub datum_name: String,
+    pub params: DatumParams,
+}
+
+// Poll response of CFG-DAT (0x06 0x06), 52 bytes
+pub fn parse_cfg_dat(payload: &[u8]) -> Option<CfgDat> {
+    if payload.len() < 52 {
+        return None;
+    }
+
+    let f32_at =
+        |offset: usize| f32::from_le_bytes(payload[offset..offset + 4].try_into().unwrap());
+    let f64_at =
+        |offset: usize| f64::from_le_bytes(payload[offset..offset + 8].try_into().unwrap());
+    let name = &payload[2..8];
+    let name_end = name.iter().position(|&b| b == 0).unwrap_or(name.len());
+
+    Some(CfgDat {
+        datum_num: u16::from_le_bytes([payload[0], payload[1]]),
+        datum_name: String::from_utf8_lossy(&name[..name_end])
+            .trim()
+            .to_string(),
+        params: DatumParams {
+            major_axis: f64_at(8),
+            flattening: f64_at(16),
+            dx: f32_at(24),
+            dy: f32_at(28),
+            dz: f32_at(32),
+            rot_x: f32_at(36),
+            rot_y: f32_at(40),
+            rot_z: f32_at(44),
+            scale: f32_at(48),
+        },
+    })
+}
+
+// UBX-CFG-DAT (0x06 0x06), user defined datum. DatumParams::WGS84 restores the default.
+#[cfg(feature = "serialport")]
+pub fn configure_datum(port: &mut dyn SerialPort, datum: DatumParams) -> io::Result<()> {
+    CfgBuilder::new(0x06)
+        .set_f64_le(0, datum.major_axis)
+        .set_f64_le(8, datum.flattening)
+        .set_f32_le(16, datum.dx)
+        .set_f32_le(20, datum.dy)
+        .set_f32_le(24, datum.dz)
+        .set_f32_le(28, datum.rot_x)
+        .set_f32_le(32, datum.rot_y)
+        .set_f32_le(36, datum.rot_z)
+        .set_f32_le(40, datum.scale)
+        .send(port)
+}
+
+// Polls CFG-DAT for the datum in use
+#[cfg(feature = "serialport")]
+pub fn get_datum(port: &mut dyn SerialPort) -> Result<CfgDat, UbxError> {
+    let config = PollConfig {
+        retries: 3,
+        timeout: DEFAULT_POLL_TIMEOUT,
+    };
+    let msg = poll_message(port, UBX_CLASS_CFG, 0x06, &[], &config)?;
+    parse_cfg_dat(&msg.payload).ok_or(UbxError::InvalidFrame)
+}
+
+// One system of UBX-CFG-GNSS
+#[derive(Debug, Clone, Copy)]
+pub struct GnssBlock {
+    pub gnss_id: GnssId,
+    // tracking channels reserved for this system
+    pub res_trk_ch: u8,
+    // upper limit of tracking channels for this system
+    pub max_trk_ch: u8,
+    pub enable: bool,
+}
+
+/*
+    UBX-CFG-GNSS (0x06 0x3E), which GNSS systems are tracked and how the channels are
+    shared between them. Systems not listed keep their current setting.
+
+    configure_gnss(&mut *port, &[
+        GnssBlock { gnss_id: GnssId::Gps, res_trk_ch: 8, max_trk_ch: 16, enable: true },
+        GnssBlock { gnss_id: GnssId::Glonass, res_trk_ch: 8, max_trk_ch: 14, enable: true },
+    ])?;
+
+    The u-blox 7 tracks GPS + GLONASS concurrently only with a GLONASS capable antenna
+    path; check NavSat::constellations_present() after the change.
+*/
+#[cfg(feature = "serialport")]
+pub fn configure_gnss(port: &mut dyn SerialPort, blocks: &[GnssBlock]) -> io::Result<()> {
+    let mut cfg = CfgBuilder::new(0x3E)
+        .set_u8(0, 0) // msgVer
+        .set_u8(1, 0) // numTrkChHw, read only
+        .set_u8(2, 0xFF) // numTrkChUse, all available
+        .set_u8(3, blocks.len() as u8);
+
+    for (i, block) in blocks.iter().enumerate() {
+        let offset = 4 + 8 * i;
+        cfg = cfg
+            .set_u8(offset, block.gnss_id.to_u8())
+            .set_u8(offset + 1, block.res_trk_ch)
+            .set_u8(offset + 2, block.max_trk_ch)
+            .set_u32_le(offset + 4, u32::from(block.enable));
+    }
+
+    cfg.send(port)
+}
+
+/*
+    CFG messages captured by dump_config, as (id, poll payload), in replay order. Ports
+    come last so a changed port setting cannot cut off the rest of the restore. Besides
+    these, one CFG-MSG per message of NMEA_STANDARD_MESSAGES and UBX_PERIODIC_MESSAGES
+    is captured (output rates on every port).
+
+    Messages the receiver does not answer (e.g. CFG-GNSS on single GNSS firmware) are
+    left out of the snapshot.
+*/
+#[rustfmt::skip]
+pub const CONFIG_SNAPSHOT_POLLS: [(u8, &[u8]); 20] = [
+    (0x02, &[0]), // CFG-INF, UBX
+    (0x02, &[1]), // CFG-INF, NMEA
+    (0x31, &[0]), // CFG-TP5, TIMEPULSE
+    (0x31, &[1]), // CFG-TP5, TIMEPULSE2
+    (0x08, &[]),  // CFG-RATE
+    (0x24, &[]),  // CFG-NAV5
+    (0x23, &[]),  // CFG-NAVX5
+    (0x16, &[]),  // CFG-SBAS
+    (0x3E, &[]),  // CFG-GNSS
+    (0x13, &[]),  // CFG-ANT
+    (0x17, &[]),  // CFG-NMEA
+    (0x11, &[]),  // CFG-RXM
+    (0x3B, &[]),  // CFG-PM2
+    (0x39, &[]),  // CFG-ITFM
+    (0x1B, &[]),  // CFG-USB
+    (0x06, &[]),  // CFG-DAT
+    (0x00, &[0]), // CFG-PRT, I2C
+    (0x00, &[4]), // CFG-PRT, SPI
+    (0x00, &[3]), // CFG-PRT, USB
+    (0x00, &[1]), // CFG-PRT, UART1
+];
+
+/*
+    Reads the receiver configuration listed in CONFIG_SNAPSHOT_POLLS (plus the message
+    rates) for provisioning other modules with restore_config.
+
+    The snapshot is the poll answers as plain UBX frames, back to back. A CFG poll is
+    answered in the same format as the message setting it, except CFG-DAT, which
+    restore_config converts with cfg_set_payload.
+*/
+#[cfg(feature = "serialport")]
+pub fn dump_config(port: &mut dyn SerialPort) -> Result<Vec<u8>, UbxError> {
+    let config = PollConfig {
+        retries: 2,
+        timeout: DEFAULT_POLL_TIMEOUT,
+    };
+    let messages = NMEA_STANDARD_MESSAGES
+        .iter()
+        .chain(UBX_PERIODIC_MESSAGES.iter())
+        .map(|(class, id)| (0x01, vec![*class, *id]));
+    let polls = messages.chain(
+        CONFIG_SNAPSHOT_POLLS
+            .iter()
+            .map(|(id, payload)| (*id, payload.to_vec())),
+    );
+
+    let mut snapshot = Vec::new();
+    for (id, payload) in polls {
+        match poll_message(port, UBX_CLASS_CFG, id, &payload, &config) {
+            Ok(msg) => snapshot.extend(build_ubx_frame(msg.class, msg.id, &msg.payload)),
+            Err(UbxError::Timeout) => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(snapshot)
+}
+
+/*
+    Replays a dump_config snapshot, in order, stopping at the first frame not
+    acknowledged (UbxError::Nak / UbxError::Timeout). The configuration is not saved,
+    follow with save_config to make it permanent.
+
+    A snapshot that is not a clean sequence of CFG frames is rejected with
+    UbxError::InvalidFrame before anything is sent. If the snapshot changes the baud rate
+    of the UART in use, the last acknowledgement arrives at the new rate and is reported
+    as a timeout although the change was applied.
+*/
+#[cfg(feature = "serialport")]
+pub fn restore_config(port: &mut dyn SerialPort, snapshot: &[u8]) -> Result<(), UbxError> {
+    let mut parser = UbxParser::new();
+    parser.set_frame_timeout(None);
+    parser.push(snapshot);
+
+    let mut frames = Vec::new();
+    while let Some(msg) = parser.next_message() {
+        if msg.class != UBX_CLASS_CFG {
+            return Err(UbxError::InvalidFrame);
+        }
+        frames.push(msg);
+    }
+    let stats = parser.stats();
+    if parser.buffered() > 0 || stats.bytes_discarded > 0 || stats.nmea_sentences > 0 {
+        return Err(UbxError::InvalidFrame);
+    }
+
+    for msg in frames {
+        send_acked(port, msg.id, cfg_set_payload(msg.id, &msg.payload))?;
+    }
+    Ok(())
+}
+
+/*
+    The payload setting what a CFG poll answered, for sending a snapshot back.
+
+    Only CFG-DAT differs: its 52 byte answer starts with datumNum and datumName, the set
+    message is the 44 bytes of parameters after them (the layout configure_datum
+    builds). Every other answer is returned as is.
+*/
+pub fn cfg_set_payload(id: u8, poll_answer: &[u8]) -> &[u8] {
+    match id {
+        0x06 if poll_answer.len() >= 52 => &poll_answer[8..52],
+        _ => poll_answer,
+    }
+}
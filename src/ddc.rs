@@ -0,0 +1,100 @@
+use i2cdev::core::I2CDevice;
+use i2cdev::linux::LinuxI2CDevice;
+use std::io::{self, Read, Write};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+// DDC register map
+const REG_BYTES_AVAILABLE: u8 = 0xFD;
+const REG_DATA_STREAM: u8 = 0xFF;
+
+// Default u-blox DDC slave address
+pub const DDC_DEFAULT_ADDRESS: u16 = 0x42;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/*
+    u-blox DDC (I2C compatible) transport, for boards wiring the module to an I2C bus
+    such as the Raspberry Pi (/dev/i2c-1).
+
+    Registers 0xFD / 0xFE hold the number of bytes pending in the receiver's output
+    buffer (big endian), register 0xFF streams them out. Writes of two bytes or more go
+    straight into the receiver's input, a single byte write only sets the register address.
+
+    Reads behave like a serial port: read() waits up to the timeout for data and returns
+    ErrorKind::TimedOut if none arrived, so DdcPort can feed UbxParser the same way a
+    SerialPort does.
+*/
+pub struct DdcPort {
+    device: LinuxI2CDevice,
+    timeout: Duration,
+}
+
+impl DdcPort {
+    pub fn open(bus_path: &str, address: u16) -> io::Result<DdcPort> {
+        Ok(DdcPort {
+            device: LinuxI2CDevice::new(bus_path, address)?,
+            timeout: Duration::from_millis(200),
+        })
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    pub fn bytes_available(&mut self) -> io::Result<usize> {
+        let mut count = [0u8; 2];
+        self.device.write(&[REG_BYTES_AVAILABLE])?;
+        self.device.read(&mut count)?;
+        Ok(u16::from_be_bytes(count) as usize)
+    }
+}
+
+impl Read for DdcPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let available = self.bytes_available()?;
+            if available > 0 {
+                let n = available.min(buf.len());
+                self.device.write(&[REG_DATA_STREAM])?;
+                self.device.read(&mut buf[..n])?;
+                return Ok(n);
+            }
+
+            if Instant::now() >= deadline {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "no data available on DDC",
+                ));
+            }
+            sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+impl Write for DdcPort {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.len() < 2 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "DDC writes shorter than two bytes are register address sets",
+            ));
+        }
+        self.device.write(buf)?;
+        Ok(buf.len())
+    }
+
+    // Every write is a complete I2C transaction, nothing is buffered
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,249 @@
+use crate::{
+    BufferUsage, InfMessage, MonHw, MonTxBuf, MonVer, NavAopStatus, NavClock, NavDgps, NavDop,
+    NavPvt, NavSat, NavStatus, NavSvInfo, NavTimeGlo, NavTimeGps, NavTimeLs, NavTimeUtc, Position,
+    RxmSfrb, UBX_CLASS_INF, UbxMessage, UbxParser, Velocity, parse_inf, parse_mon_hw,
+    parse_mon_rxbuf, parse_mon_txbuf, parse_mon_ver, parse_nav_aopstatus, parse_nav_clock,
+    parse_nav_dgps, parse_nav_dop, parse_nav_posllh, parse_nav_pvt, parse_nav_sat,
+    parse_nav_status, parse_nav_svinfo, parse_nav_timeglo, parse_nav_timegps, parse_nav_timels,
+    parse_nav_timeutc, parse_nav_velned, parse_rxm_sfrb,
+};
+#[cfg(feature = "serialport")]
+use serialport::SerialPort;
+use std::any::Any;
+use std::collections::HashMap;
+#[cfg(feature = "serialport")]
+use std::io;
+#[cfg(feature = "serialport")]
+use std::ops::ControlFlow;
+
+pub const UBX_CLASS_ACK: u8 = 0x05;
+pub const UBX_ID_ACK_NAK: u8 = 0x00;
+pub const UBX_ID_ACK_ACK: u8 = 0x01;
+
+// A received frame decoded into the strongest type known for its class / id
+#[derive(Debug)]
+pub enum DecodedMessage {
+    // UBX-ACK-ACK / UBX-ACK-NAK for the message cls / id
+    Ack { cls: u8, id: u8, accepted: bool },
+    Position(Position),
+    Velocity(Velocity),
+    NavStatus(NavStatus),
+    NavDop(NavDop),
+    NavClock(NavClock),
+    NavDgps(NavDgps),
+    NavPvt(NavPvt),
+    NavSat(NavSat),
+    NavTimeGps(NavTimeGps),
+    NavTimeUtc(NavTimeUtc),
+    NavTimeLs(NavTimeLs),
+    NavTimeGlo(NavTimeGlo),
+    NavAopStatus(NavAopStatus),
+    NavSvInfo(NavSvInfo),
+    MonVer(MonVer),
+    MonHw(MonHw),
+    MonRxBuf(BufferUsage),
+    MonTxBuf(MonTxBuf),
+    RxmSfrb(RxmSfrb),
+    Inf(InfMessage),
+    // Unknown class / id, or a known one whose payload failed to parse
+    Raw(UbxMessage),
+}
+
+impl DecodedMessage {
+    // GPS time of week (ms) of the navigation epoch the message belongs to, for messages
+    // carrying one
+    pub fn i_tow(&self) -> Option<u32> {
+        match self {
+            DecodedMessage::Velocity(m) => Some(m.i_tow),
+            DecodedMessage::NavStatus(m) => Some(m.i_tow),
+            DecodedMessage::NavDop(m) => Some(m.i_tow),
+            DecodedMessage::NavClock(m) => Some(m.i_tow),
+            DecodedMessage::NavDgps(m) => Some(m.i_tow),
+            DecodedMessage::NavPvt(m) => Some(m.i_tow),
+            DecodedMessage::NavSat(m) => Some(m.i_tow),
+            DecodedMessage::NavTimeGps(m) => Some(m.i_tow),
+            DecodedMessage::NavTimeUtc(m) => Some(m.i_tow),
+            DecodedMessage::NavTimeLs(m) => Some(m.i_tow),
+            DecodedMessage::NavTimeGlo(m) => Some(m.i_tow),
+            DecodedMessage::NavAopStatus(m) => Some(m.i_tow),
+            DecodedMessage::NavSvInfo(m) => Some(m.i_tow),
+            _ => None,
+        }
+    }
+}
+
+pub fn decode_message(msg: UbxMessage) -> DecodedMessage {
+    let p = &msg.payload;
+    let decoded = match (msg.class, msg.id) {
+        (UBX_CLASS_ACK, UBX_ID_ACK_NAK | UBX_ID_ACK_ACK) if p.len() >= 2 => {
+            Some(DecodedMessage::Ack {
+                cls: p[0],
+                id: p[1],
+                accepted: msg.id == UBX_ID_ACK_ACK,
+            })
+        }
+        (0x01, 0x02) => parse_nav_posllh(p).map(DecodedMessage::Position),
+        (0x01, 0x03) => parse_nav_status(p).map(DecodedMessage::NavStatus),
+        (0x01, 0x04) => parse_nav_dop(p).map(DecodedMessage::NavDop),
+        (0x01, 0x07) => parse_nav_pvt(p).map(DecodedMessage::NavPvt),
+        (0x01, 0x12) => parse_nav_velned(p).map(DecodedMessage::Velocity),
+        (0x01, 0x20) => parse_nav_timegps(p).map(DecodedMessage::NavTimeGps),
+        (0x01, 0x21) => parse_nav_timeutc(p).map(DecodedMessage::NavTimeUtc),
+        (0x01, 0x22) => parse_nav_clock(p).map(DecodedMessage::NavClock),
+        (0x01, 0x23) => parse_nav_timeglo(p).map(DecodedMessage::NavTimeGlo),
+        (0x01, 0x26) => parse_nav_timels(p).map(DecodedMessage::NavTimeLs),
+        (0x01, 0x30) => parse_nav_svinfo(p).map(DecodedMessage::NavSvInfo),
+        (0x01, 0x31) => parse_nav_dgps(p).map(DecodedMessage::NavDgps),
+        (0x01, 0x35) => parse_nav_sat(p).map(DecodedMessage::NavSat),
+        (0x01, 0x60) => parse_nav_aopstatus(p).map(DecodedMessage::NavAopStatus),
+        (0x02, 0x11) => parse_rxm_sfrb(p).map(DecodedMessage::RxmSfrb),
+        (UBX_CLASS_INF, id) => parse_inf(id, p).map(DecodedMessage::Inf),
+        (0x0A, 0x04) => parse_mon_ver(p).map(DecodedMessage::MonVer),
+        (0x0A, 0x07) => parse_mon_rxbuf(p).map(DecodedMessage::MonRxBuf),
+        (0x0A, 0x08) => parse_mon_txbuf(p).map(DecodedMessage::MonTxBuf),
+        (0x0A, 0x09) => parse_mon_hw(p).map(DecodedMessage::MonHw),
+        _ => None,
+    };
+
+    decoded.unwrap_or(DecodedMessage::Raw(msg))
+}
+
+type DecodeFn = Box<dyn Fn(&[u8]) -> Option<Box<dyn Any>> + Send + Sync>;
+
+/*
+    Decode dispatch table, (class, id) -> parse function, open to messages the crate does
+    not know (firmware specific, proprietary, newer protocol versions).
+
+    new() comes with every built-in parser registered, register() adds or replaces one:
+
+    let mut decoder = Decoder::new();
+    decoder.register(0xF0, 0x01, parse_my_message);
+    if let Some(decoded) = decoder.decode(&msg) {
+        if let Some(mine) = decoded.downcast_ref::<MyMessage>() { ... }
+    }
+
+    Results are the parse function's type boxed as Any, e.g. Position for NAV-POSLLH.
+    DecodedMessage / decode_message remain the closed, matchable variant of this.
+*/
+pub struct Decoder {
+    decoders: HashMap<(u8, u8), DecodeFn>,
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        let mut decoder = Self::empty();
+        decoder.register(0x01, 0x02, parse_nav_posllh);
+        decoder.register(0x01, 0x03, parse_nav_status);
+        decoder.register(0x01, 0x04, parse_nav_dop);
+        decoder.register(0x01, 0x07, parse_nav_pvt);
+        decoder.register(0x01, 0x12, parse_nav_velned);
+        decoder.register(0x01, 0x20, parse_nav_timegps);
+        decoder.register(0x01, 0x21, parse_nav_timeutc);
+        decoder.register(0x01, 0x22, parse_nav_clock);
+        decoder.register(0x01, 0x23, parse_nav_timeglo);
+        decoder.register(0x01, 0x26, parse_nav_timels);
+        decoder.register(0x01, 0x30, parse_nav_svinfo);
+        decoder.register(0x01, 0x31, parse_nav_dgps);
+        decoder.register(0x01, 0x35, parse_nav_sat);
+        decoder.register(0x01, 0x60, parse_nav_aopstatus);
+        decoder.register(0x02, 0x11, parse_rxm_sfrb);
+        for id in 0x00..=0x04 {
+            decoder.register(UBX_CLASS_INF, id, move |p| parse_inf(id, p));
+        }
+        decoder.register(0x0A, 0x04, parse_mon_ver);
+        decoder.register(0x0A, 0x07, parse_mon_rxbuf);
+        decoder.register(0x0A, 0x08, parse_mon_txbuf);
+        decoder.register(0x0A, 0x09, parse_mon_hw);
+        decoder
+    }
+
+    // Without the built-in parsers
+    pub fn empty() -> Self {
+        Decoder {
+            decoders: HashMap::new(),
+        }
+    }
+
+    // Registers parse for class / id, replacing any previous one. parse returns None for
+    // a payload it cannot decode.
+    pub fn register<T, F>(&mut self, class: u8, id: u8, parse: F)
+    where
+        T: Any,
+        F: Fn(&[u8]) -> Option<T> + Send + Sync + 'static,
+    {
+        self.decoders.insert(
+            (class, id),
+            Box::new(move |payload| parse(payload).map(|v| Box::new(v) as Box<dyn Any>)),
+        );
+    }
+
+    pub fn unregister(&mut self, class: u8, id: u8) {
+        self.decoders.remove(&(class, id));
+    }
+
+    pub fn is_registered(&self, class: u8, id: u8) -> bool {
+        self.decoders.contains_key(&(class, id))
+    }
+
+    // None if nothing is registered for the message or its payload failed to parse
+    pub fn decode(&self, msg: &UbxMessage) -> Option<Box<dyn Any>> {
+        self.decoders.get(&(msg.class, msg.id))?(&msg.payload)
+    }
+}
+
+// Frames and decodes every complete UBX message in data, garbage and incomplete frames
+// are skipped
+pub fn decode_all(data: &[u8]) -> Vec<DecodedMessage> {
+    let mut parser = UbxParser::new();
+    parser.set_frame_timeout(None);
+    parser.push(data);
+
+    let mut messages = Vec::new();
+    while let Some(msg) = parser.next_decoded() {
+        messages.push(msg);
+    }
+    messages
+}
+
+/*
+    Reads and decodes from port until on_message returns ControlFlow::Break, calling it
+    for every frame received.
+
+    Read timeouts are not errors, the pump keeps waiting. Returns Ok(()) once the callback
+    breaks, or the first other I/O error (device unplugged ...).
+
+    run_pump(&mut *port, |msg| {
+        if let DecodedMessage::NavPvt(pvt) = msg {
+            println!("{}", pvt.num_sv);
+        }
+        ControlFlow::Continue(())
+    })?;
+*/
+#[cfg(feature = "serialport")]
+pub fn run_pump<F>(port: &mut dyn SerialPort, mut on_message: F) -> io::Result<()>
+where
+    F: FnMut(DecodedMessage) -> ControlFlow<()>,
+{
+    let mut parser = UbxParser::new();
+    let mut buf = [0u8; 1024];
+
+    loop {
+        match port.read(&mut buf) {
+            Ok(n) => parser.push(&buf[..n]),
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => continue,
+            Err(e) => return Err(e),
+        }
+
+        while let Some(msg) = parser.next_decoded() {
+            if on_message(msg).is_break() {
+                return Ok(());
+            }
+        }
+    }
+}
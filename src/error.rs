@@ -0,0 +1,86 @@
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum UbxError {
+    Io(io::Error),
+    // No (matching) response within the allowed time
+    Timeout,
+    // Frame declares more bytes than the read buffer can hold
+    Truncated { declared: usize, capacity: usize },
+    // Bytes read are not a valid UBX frame (no sync, bad checksum)
+    InvalidFrame,
+    // The u-blox 7 does not provide this information
+    Unsupported(&'static str),
+    // The receiver rejected the message cls / id with ACK-NAK
+    Nak { class: u8, id: u8 },
+    // The port opened but stayed silent, most likely held by another process
+    PortBusy(String),
+    // The port answered no UBX poll, something other than a u-blox is attached
+    NotAUblox(String),
+    // MON-VER carries no PROTVER extension (u-blox 6 and older firmware)
+    NoProtocolVersion,
+}
+
+impl fmt::Display for UbxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UbxError::Io(e) => write!(f, "I/O error: {}", e),
+            UbxError::Timeout => write!(f, "timed out waiting for the receiver"),
+            UbxError::Truncated { declared, capacity } => write!(
+                f,
+                "frame of {} bytes exceeds read capacity of {} bytes",
+                declared, capacity
+            ),
+            UbxError::InvalidFrame => write!(f, "invalid UBX frame"),
+            UbxError::Unsupported(what) => write!(f, "not supported by the u-blox 7: {}", what),
+            UbxError::Nak { class, id } => write!(
+                f,
+                "receiver rejected message 0x{:02X} 0x{:02X} (ACK-NAK)",
+                class, id
+            ),
+            UbxError::PortBusy(path) => write!(
+                f,
+                "no data from {}, the port may be held by another process \
+                 (on Linux ModemManager probes /dev/ttyACM* devices, stop or disable it \
+                 or add a udev rule with ENV{{ID_MM_DEVICE_IGNORE}}=\"1\")",
+                path
+            ),
+            UbxError::NotAUblox(path) => write!(
+                f,
+                "no UBX answer from {}, check the device and baud rate",
+                path
+            ),
+            UbxError::NoProtocolVersion => write!(
+                f,
+                "receiver reports no protocol version (no PROTVER in MON-VER)"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for UbxError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            UbxError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for UbxError {
+    fn from(e: io::Error) -> Self {
+        UbxError::Io(e)
+    }
+}
+
+// For helpers returning io::Result, the variant is kept as the error payload
+impl From<UbxError> for io::Error {
+    fn from(e: UbxError) -> Self {
+        match e {
+            UbxError::Io(e) => e,
+            UbxError::Timeout => io::Error::new(io::ErrorKind::TimedOut, e),
+            other => io::Error::other(other),
+        }
+    }
+}
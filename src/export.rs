@@ -0,0 +1,242 @@
+#[cfg(feature = "serialport")]
+use crate::{DecodedMessage, UbxParser, gga_sentence, rmc_sentence};
+use crate::{FixType, NavPvt, Position, Velocity, format_utc};
+#[cfg(feature = "serialport")]
+use serialport::SerialPort;
+use std::io::{self, Write};
+#[cfg(feature = "serialport")]
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsvColumn {
+    Time,
+    Lat,
+    Lon,
+    Alt,
+    HAcc,
+    VAcc,
+    Speed,
+    Heading,
+    NumSv,
+    FixType,
+}
+
+impl CsvColumn {
+    pub const ALL: [CsvColumn; 10] = [
+        CsvColumn::Time,
+        CsvColumn::Lat,
+        CsvColumn::Lon,
+        CsvColumn::Alt,
+        CsvColumn::HAcc,
+        CsvColumn::VAcc,
+        CsvColumn::Speed,
+        CsvColumn::Heading,
+        CsvColumn::NumSv,
+        CsvColumn::FixType,
+    ];
+
+    pub fn header(&self) -> &'static str {
+        match self {
+            CsvColumn::Time => "time",
+            CsvColumn::Lat => "lat",
+            CsvColumn::Lon => "lon",
+            CsvColumn::Alt => "alt_msl_m",
+            CsvColumn::HAcc => "h_acc_m",
+            CsvColumn::VAcc => "v_acc_m",
+            CsvColumn::Speed => "speed_mps",
+            CsvColumn::Heading => "heading_deg",
+            CsvColumn::NumSv => "num_sv",
+            CsvColumn::FixType => "fix_type",
+        }
+    }
+}
+
+// One row of fix data, fields not known for a fix are written as empty cells
+#[derive(Debug, Clone, Default)]
+pub struct FixRecord {
+    pub time: Option<SystemTime>,
+    pub position: Option<Position>,
+    pub velocity: Option<Velocity>,
+    pub num_sv: Option<u8>,
+    pub fix_type: Option<FixType>,
+}
+
+impl From<&Position> for FixRecord {
+    fn from(pos: &Position) -> Self {
+        FixRecord {
+            position: Some(pos.clone()),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&NavPvt> for FixRecord {
+    fn from(pvt: &NavPvt) -> Self {
+        FixRecord {
+            time: Some(pvt.utc()),
+            position: Some(Position::from(pvt)),
+            velocity: Some(Velocity {
+                i_tow: pvt.i_tow,
+                vel_n: pvt.vel_n,
+                vel_e: pvt.vel_e,
+                vel_d: pvt.vel_d,
+                speed: (pvt.ground_speed.powi(2) + pvt.vel_d.powi(2)).sqrt(),
+                ground_speed: pvt.ground_speed,
+                heading: pvt.heading,
+                speed_accuracy: pvt.speed_accuracy,
+                heading_accuracy: pvt.heading_accuracy,
+            }),
+            num_sv: Some(pvt.num_sv),
+            fix_type: Some(pvt.fix_type),
+        }
+    }
+}
+
+// RFC 4180 quoting, only applied when the field needs it
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/*
+    CSV export of fixes with a selectable set of columns.
+
+    The header row is written before the first record (or by finish() for an empty log).
+    Coordinates use 7 decimals (~1 cm), heights / accuracies / speeds 3 decimals (mm),
+    heading 2 decimals, time is ISO 8601 UTC.
+*/
+pub struct CsvWriter<W: Write> {
+    writer: W,
+    columns: Vec<CsvColumn>,
+    header_written: bool,
+}
+
+impl<W: Write> CsvWriter<W> {
+    pub fn new(writer: W, columns: &[CsvColumn]) -> Self {
+        CsvWriter {
+            writer,
+            columns: columns.to_vec(),
+            header_written: false,
+        }
+    }
+
+    pub fn with_all_columns(writer: W) -> Self {
+        Self::new(writer, &CsvColumn::ALL)
+    }
+
+    fn write_header(&mut self) -> io::Result<()> {
+        let header: Vec<&str> = self.columns.iter().map(|c| c.header()).collect();
+        writeln!(self.writer, "{}", header.join(","))?;
+        self.header_written = true;
+        Ok(())
+    }
+
+    pub fn write_record(&mut self, record: &FixRecord) -> io::Result<()> {
+        if !self.header_written {
+            self.write_header()?;
+        }
+
+        let pos = record.position.as_ref();
+        let vel = record.velocity.as_ref();
+        let fields: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| {
+                let value = match column {
+                    CsvColumn::Time => record.time.map(format_utc),
+                    CsvColumn::Lat => pos.map(|p| format!("{:.7}", p.lat)),
+                    CsvColumn::Lon => pos.map(|p| format!("{:.7}", p.lon)),
+                    CsvColumn::Alt => pos.map(|p| format!("{:.3}", p.height_msl)),
+                    CsvColumn::HAcc => pos.map(|p| format!("{:.3}", p.horizontal_accuracy)),
+                    CsvColumn::VAcc => pos.map(|p| format!("{:.3}", p.vertical_accuracy)),
+                    CsvColumn::Speed => vel.map(|v| format!("{:.3}", v.ground_speed)),
+                    CsvColumn::Heading => vel.map(|v| format!("{:.2}", v.heading.degrees())),
+                    CsvColumn::NumSv => record.num_sv.map(|n| n.to_string()),
+                    CsvColumn::FixType => record.fix_type.map(|f| f.to_string()),
+                };
+                csv_field(&value.unwrap_or_default())
+            })
+            .collect();
+
+        writeln!(self.writer, "{}", fields.join(","))
+    }
+
+    // Writes the header if no record was written, flushes and hands the writer back
+    pub fn finish(mut self) -> io::Result<W> {
+        if !self.header_written {
+            self.write_header()?;
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+// GGA + RMC for one NAV-PVT solution, hdop from the latest NAV-DOP if any (else PDOP)
+#[cfg(feature = "serialport")]
+fn pvt_sentences(pvt: &NavPvt, hdop: Option<f64>) -> String {
+    let pos = Position::from(pvt);
+    let valid = pvt.is_valid_fix();
+    let quality = match (valid, pvt.is_differential()) {
+        (false, _) => 0,
+        (true, false) => 1,
+        (true, true) => 2,
+    };
+    let utc = pvt.utc();
+
+    gga_sentence(&pos, utc, quality, pvt.num_sv, hdop.unwrap_or(pvt.pdop))
+        + &rmc_sentence(&pos, utc, valid, pvt.ground_speed, pvt.heading)
+}
+
+/*
+    NMEA over TCP for chart plotters and similar (e.g. OpenCPN connecting to bind_addr).
+
+    Reads UBX from port and sends a GPGGA and a GPRMC sentence to every connected client
+    for each NAV-PVT received, enable NAV-PVT (and NAV-DOP for a real HDOP) on the port.
+    Clients may connect and disconnect at any time, one that fails a write is dropped.
+    Runs until reading the port fails.
+*/
+#[cfg(feature = "serialport")]
+pub fn serve_nmea_tcp(port: &mut dyn SerialPort, bind_addr: impl ToSocketAddrs) -> io::Result<()> {
+    let listener = TcpListener::bind(bind_addr)?;
+    listener.set_nonblocking(true)?;
+
+    let mut clients: Vec<TcpStream> = Vec::new();
+    let mut parser = UbxParser::new();
+    let mut buf = [0u8; 1024];
+    let mut hdop = None;
+
+    loop {
+        loop {
+            match listener.accept() {
+                Ok((client, _)) => {
+                    client.set_nodelay(true)?;
+                    clients.push(client);
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        match port.read(&mut buf) {
+            Ok(n) => parser.push(&buf[..n]),
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => continue,
+            Err(e) => return Err(e),
+        }
+
+        while let Some(msg) = parser.next_decoded() {
+            let sentences = match msg {
+                DecodedMessage::NavDop(dop) => {
+                    hdop = Some(dop.hdop);
+                    continue;
+                }
+                DecodedMessage::NavPvt(pvt) => pvt_sentences(&pvt, hdop),
+                _ => continue,
+            };
+            clients.retain_mut(|client| client.write_all(sentences.as_bytes()).is_ok());
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use crate::{DecodedMessage, Position};
+use std::time::{Duration, Instant};
+
+/*
+    Suppresses positions produced before the receiver has a valid fix.
+
+    During acquisition NAV-POSLLH happily reports a position (often 0,0 or the last
+    known one). The gate remembers the gpsFixOk flag of the latest NAV-STATUS or NAV-PVT
+    and only lets positions through while that flag is set and no older than max_age.
+
+    Requires NAV-STATUS or NAV-PVT to be enabled alongside NAV-POSLLH, without them no
+    position ever passes.
+*/
+pub struct FixGate {
+    max_age: Duration,
+    fix_ok_at: Option<Instant>,
+}
+
+impl FixGate {
+    pub fn new(max_age: Duration) -> Self {
+        FixGate {
+            max_age,
+            fix_ok_at: None,
+        }
+    }
+
+    pub fn fix_ok(&self) -> bool {
+        self.fix_ok_at
+            .is_some_and(|at| at.elapsed() <= self.max_age)
+    }
+
+    fn set_fix_ok(&mut self, ok: bool) {
+        self.fix_ok_at = ok.then(Instant::now);
+    }
+
+    // Feeds one decoded message, returns a position only when it may be trusted
+    pub fn process(&mut self, msg: &DecodedMessage) -> Option<Position> {
+        match msg {
+            DecodedMessage::NavStatus(status) => {
+                self.set_fix_ok(status.gps_fix_ok());
+                None
+            }
+            DecodedMessage::NavPvt(pvt) => {
+                self.set_fix_ok(pvt.gnss_fix_ok());
+                self.fix_ok().then(|| Position::from(pvt))
+            }
+            DecodedMessage::Position(pos) => self.fix_ok().then(|| pos.clone()),
+            _ => None,
+        }
+    }
+}
+
+// Default process noise of KalmanLite, m² per update
+pub const DEFAULT_KALMAN_PROCESS_NOISE: f64 = 0.01;
+
+/*
+    Accuracy weighted position fusion, a per-axis Kalman filter with a stationary model.
+
+    Each fix is a measurement with variance horizontal_accuracy² (vertical_accuracy² for
+    the height), so a 2 m fix counts four times as much as a 4 m one, and the fused
+    estimate's accuracy shrinks as fixes accumulate. process_noise (m² added to the
+    estimate's variance per update) lets it follow slow drift or movement, 0 averages a
+    truly fixed point forever.
+
+    Fixes without a usable accuracy (NaN, e.g. from NMEA) leave the estimate unchanged.
+*/
+#[derive(Debug, Clone)]
+pub struct KalmanLite {
+    process_noise: f64,
+    estimate: Option<Position>,
+    h_var: f64,
+    v_var: f64,
+}
+
+impl Default for KalmanLite {
+    fn default() -> Self {
+        Self::new(DEFAULT_KALMAN_PROCESS_NOISE)
+    }
+}
+
+// Variance of a fix from its accuracy, infinite (no information) if unknown
+fn measurement_variance(accuracy: f64) -> f64 {
+    if accuracy.is_finite() {
+        accuracy.powi(2)
+    } else {
+        f64::INFINITY
+    }
+}
+
+// Blends value (variance var) with a measurement, returns the new value and variance
+fn kalman_step(value: f64, var: f64, measured: f64, measured_var: f64) -> (f64, f64) {
+    if var.is_infinite() || var + measured_var <= 0.0 {
+        return (measured, measured_var);
+    }
+    let gain = var / (var + measured_var);
+    (value + gain * (measured - value), (1.0 - gain) * var)
+}
+
+impl KalmanLite {
+    pub fn new(process_noise: f64) -> Self {
+        KalmanLite {
+            process_noise: process_noise.max(0.0),
+            estimate: None,
+            h_var: f64::INFINITY,
+            v_var: f64::INFINITY,
+        }
+    }
+
+    // Feeds a fix, returns the fused position with its accuracy estimate
+    pub fn update(&mut self, pos: &Position) -> Position {
+        let h_meas = measurement_variance(pos.horizontal_accuracy);
+        let v_meas = measurement_variance(pos.vertical_accuracy);
+
+        let Some(estimate) = &mut self.estimate else {
+            self.h_var = h_meas;
+            self.v_var = v_meas;
+            self.estimate = Some(pos.clone());
+            return pos.clone();
+        };
+
+        if h_meas.is_finite() {
+            let var = self.h_var + self.process_noise;
+            (estimate.lat, _) = kalman_step(estimate.lat, var, pos.lat, h_meas);
+            (estimate.lon, self.h_var) = kalman_step(estimate.lon, var, pos.lon, h_meas);
+            estimate.horizontal_accuracy = self.h_var.sqrt();
+        }
+        if v_meas.is_finite() {
+            let var = self.v_var + self.process_noise;
+            (estimate.height_msl, self.v_var) =
+                kalman_step(estimate.height_msl, var, pos.height_msl, v_meas);
+            estimate.vertical_accuracy = self.v_var.sqrt();
+        }
+        estimate.clone()
+    }
+
+    pub fn estimate(&self) -> Option<&Position> {
+        self.estimate.as_ref()
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new(self.process_noise);
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum DecimationMode {
+    Interval(Duration),
+    Count(u64),
+}
+
+/*
+    Thins a high rate stream to one item per time interval or every nth item.
+
+    for msg in Decimator::per_interval(Duration::from_secs(1)).wrap(reader) { ... }
+
+    The first item always passes. Intervals are measured with the host clock when each
+    item is pulled (accept_at takes the time explicitly, e.g. TimestampedMessage's
+    received_at), and are kept on a fixed schedule so jitter does not make the output
+    drift: at 10 Hz and 1 s roughly every 10th fix passes, never 9 or 11 in a row.
+*/
+#[derive(Debug, Clone)]
+pub struct Decimator {
+    mode: DecimationMode,
+    seen: u64,
+    next_due: Option<Instant>,
+}
+
+impl Decimator {
+    pub fn per_interval(interval: Duration) -> Self {
+        Decimator {
+            mode: DecimationMode::Interval(interval),
+            seen: 0,
+            next_due: None,
+        }
+    }
+
+    // Keeps items 1, n + 1, 2n + 1 ..., n of 0 is taken as 1 (everything passes)
+    pub fn every_nth(n: u64) -> Self {
+        Decimator {
+            mode: DecimationMode::Count(n.max(1)),
+            seen: 0,
+            next_due: None,
+        }
+    }
+
+    pub fn accept(&mut self) -> bool {
+        self.accept_at(Instant::now())
+    }
+
+    pub fn accept_at(&mut self, now: Instant) -> bool {
+        match self.mode {
+            DecimationMode::Count(n) => {
+                let pass = self.seen.is_multiple_of(n);
+                self.seen += 1;
+                pass
+            }
+            DecimationMode::Interval(interval) => match self.next_due {
+                Some(due) if now < due => false,
+                Some(due) if now < due + interval => {
+                    self.next_due = Some(due + interval);
+                    true
+                }
+                // first item, or a gap of more than an interval: restart the schedule
+                _ => {
+                    self.next_due = Some(now + interval);
+                    true
+                }
+            },
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.seen = 0;
+        self.next_due = None;
+    }
+
+    pub fn wrap<I: Iterator>(self, inner: I) -> Decimated<I> {
+        Decimated {
+            inner,
+            decimator: self,
+        }
+    }
+}
+
+// Iterator adapter of Decimator::wrap, items not accepted are dropped
+#[derive(Debug, Clone)]
+pub struct Decimated<I> {
+    inner: I,
+    decimator: Decimator,
+}
+
+impl<I> Decimated<I> {
+    pub fn into_inner(self) -> I {
+        self.inner
+    }
+}
+
+impl<I: Iterator> Iterator for Decimated<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        loop {
+            let item = self.inner.next()?;
+            if self.decimator.accept() {
+                return Some(item);
+            }
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use crate::{Position, Velocity, msl_to_ellipsoid_height};
+use std::fmt;
+use std::time::Duration;
+
+// Mean earth radius (IUGG), meters
+pub const EARTH_RADIUS_M: f64 = 6_371_008.8;
+
+// Heading / course in degrees, always normalized into [0, 360)
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Bearing(f64);
+
+impl Bearing {
+    pub fn new(degrees: f64) -> Bearing {
+        let normalized = degrees.rem_euclid(360.0);
+        // rem_euclid can round up to exactly 360.0 for tiny negative inputs
+        Bearing(if normalized >= 360.0 { 0.0 } else { normalized })
+    }
+
+    pub fn from_radians(radians: f64) -> Bearing {
+        Bearing::new(radians.to_degrees())
+    }
+
+    pub fn degrees(&self) -> f64 {
+        self.0
+    }
+
+    pub fn radians(&self) -> f64 {
+        self.0.to_radians()
+    }
+
+    // Signed shortest rotation from other to self, in [-180, 180). Positive is clockwise.
+    pub fn difference(&self, other: &Bearing) -> f64 {
+        (self.0 - other.0 + 180.0).rem_euclid(360.0) - 180.0
+    }
+}
+
+impl fmt::Display for Bearing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.1}°", self.0)
+    }
+}
+
+// Length of one degree of latitude / longitude in meters at the given latitude (WGS84
+// series expansion)
+pub fn meters_per_degree(lat: f64) -> (f64, f64) {
+    let phi = lat.to_radians();
+    let lat_m = 111_132.92 - 559.82 * (2.0 * phi).cos() + 1.175 * (4.0 * phi).cos();
+    let lon_m = 111_412.84 * phi.cos() - 93.5 * (3.0 * phi).cos();
+    (lat_m, lon_m)
+}
+
+impl Position {
+    pub fn latitude(&self) -> Latitude {
+        Latitude(self.lat)
+    }
+
+    pub fn longitude(&self) -> Longitude {
+        Longitude(self.lon)
+    }
+
+    // Great circle (Haversine) distance in meters, heights are ignored
+    pub fn distance_to(&self, other: &Position) -> f64 {
+        let lat1 = self.lat.to_radians();
+        let lat2 = other.lat.to_radians();
+        let dlat = lat2 - lat1;
+        let dlon = (other.lon - self.lon).to_radians();
+
+        let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS_M * a.sqrt().min(1.0).asin()
+    }
+
+    // Same place within meters (horizontal distance only)
+    pub fn approx_eq(&self, other: &Position, meters: f64) -> bool {
+        self.distance_to(other) <= meters
+    }
+
+    // Initial great circle bearing from self towards other
+    pub fn bearing_to(&self, other: &Position) -> Bearing {
+        let lat1 = self.lat.to_radians();
+        let lat2 = other.lat.to_radians();
+        let dlon = (other.lon - self.lon).to_radians();
+
+        let y = dlon.sin() * lat2.cos();
+        let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * dlon.cos();
+        Bearing::from_radians(y.atan2(x))
+    }
+
+    /*
+        Point at fraction (0 = self, 1 = other) of the way along the great circle between
+        the two. Height and accuracies are interpolated linearly.
+    */
+    pub fn interpolate(&self, other: &Position, fraction: f64) -> Position {
+        let lerp = |a: f64, b: f64| a + (b - a) * fraction;
+        let delta = self.distance_to(other) / EARTH_RADIUS_M;
+
+        let (lat, lon) = if delta < 1e-12 {
+            (lerp(self.lat, other.lat), lerp(self.lon, other.lon))
+        } else {
+            let (lat1, lon1) = (self.lat.to_radians(), self.lon.to_radians());
+            let (lat2, lon2) = (other.lat.to_radians(), other.lon.to_radians());
+            let a = ((1.0 - fraction) * delta).sin() / delta.sin();
+            let b = (fraction * delta).sin() / delta.sin();
+
+            let x = a * lat1.cos() * lon1.cos() + b * lat2.cos() * lon2.cos();
+            let y = a * lat1.cos() * lon1.sin() + b * lat2.cos() * lon2.sin();
+            let z = a * lat1.sin() + b * lat2.sin();
+            (z.atan2(x.hypot(y)).to_degrees(), y.atan2(x).to_degrees())
+        };
+
+        Position {
+            lat,
+            lon,
+            height_msl: lerp(self.height_msl, other.height_msl),
+            horizontal_accuracy: lerp(self.horizontal_accuracy, other.horizontal_accuracy),
+            vertical_accuracy: lerp(self.vertical_accuracy, other.vertical_accuracy),
+        }
+    }
+
+    /*
+        Dead reckoning: advances the position along vel.heading at vel.ground_speed for dt,
+        and the height by the vertical velocity. Uses a local flat earth approximation,
+        fine for the few seconds between fixes, not for long extrapolations.
+
+        The horizontal / vertical accuracy grow by the speed accuracy times dt.
+    */
+    pub fn project(&self, vel: &Velocity, dt: Duration) -> Position {
+        let secs = dt.as_secs_f64();
+        let distance = vel.ground_speed * secs;
+        let heading = vel.heading.radians();
+        let (lat_m, lon_m) = meters_per_degree(self.lat);
+
+        let north = distance * heading.cos();
+        let east = distance * heading.sin();
+        // Longitude scale vanishes at the poles
+        let dlon = if lon_m.abs() > f64::EPSILON {
+            east / lon_m
+        } else {
+            0.0
+        };
+
+        Position {
+            lat: self.lat + north / lat_m,
+            lon: (self.lon + dlon + 540.0).rem_euclid(360.0) - 180.0,
+            height_msl: self.height_msl - vel.vel_d * secs,
+            horizontal_accuracy: self.horizontal_accuracy + vel.speed_accuracy * secs,
+            vertical_accuracy: self.vertical_accuracy + vel.speed_accuracy * secs,
+        }
+    }
+
+    /*
+        RFC 5870 geo URI, e.g. geo:47.3655900,8.5249970,456.2;u=3.5, opens in map apps.
+
+        7 decimals (~1 cm) as delivered by the receiver. The altitude is the WGS84
+        ellipsoid height the RFC asks for (converted from height_msl), and left out if
+        unknown, as is the uncertainty parameter u (horizontal_accuracy, m).
+    */
+    pub fn to_geo_uri(&self) -> String {
+        let mut uri = format!("geo:{:.7},{:.7}", self.lat, self.lon);
+        if self.height_msl.is_finite() {
+            let altitude = msl_to_ellipsoid_height(self.lat, self.lon, self.height_msl);
+            uri.push_str(&format!(",{:.1}", altitude));
+        }
+        if self.horizontal_accuracy.is_finite() && self.horizontal_accuracy >= 0.0 {
+            uri.push_str(&format!(";u={:.1}", self.horizontal_accuracy));
+        }
+        uri
+    }
+}
+
+/*
+    Evenly spaced points along a track: the first point, then one every spacing meters
+    of distance travelled along the polyline, interpolated on the great circle between
+    the fixes around it. A leftover shorter than spacing at the end is dropped.
+
+    Segments shorter than the spacing yield no point of their own, their length carries
+    over into the next segment. A spacing that is not positive returns the points as is.
+*/
+pub fn resample_by_distance(points: &[Position], spacing_m: f64) -> Vec<Position> {
+    if spacing_m.is_nan() || spacing_m <= 0.0 {
+        return points.to_vec();
+    }
+    let Some(first) = points.first() else {
+        return Vec::new();
+    };
+
+    let mut resampled = vec![first.clone()];
+    // distance from the start of the current segment to the next output point
+    let mut to_next = spacing_m;
+    for segment in points.windows(2) {
+        let (a, b) = (&segment[0], &segment[1]);
+        let length = a.distance_to(b);
+        while to_next <= length {
+            resampled.push(a.interpolate(b, to_next / length));
+            to_next += spacing_m;
+        }
+        to_next -= length;
+    }
+    resampled
+}
+
+// Decimal degrees into (degrees, minutes, seconds, hemisphere)
+fn to_dms(value: f64, positive: char, negative: char) -> (u16, u8, f64, char) {
+    let hemisphere = if value < 0.0 { negative } else { positive };
+    let abs = value.abs();
+    let degrees = abs.trunc();
+    let minutes = ((abs - degrees) * 60.0).trunc();
+    let seconds = (abs - degrees - minutes / 60.0) * 3600.0;
+    (degrees as u16, minutes as u8, seconds.max(0.0), hemisphere)
+}
+
+// d°m's.s"H with seconds rounded to 0.1, carrying into minutes / degrees on 60.0
+fn fmt_dms(f: &mut fmt::Formatter<'_>, dms: (u16, u8, f64, char)) -> fmt::Result {
+    let (mut degrees, mut minutes, seconds, hemisphere) = dms;
+    let mut tenths = (seconds * 10.0).round() as u32;
+    if tenths >= 600 {
+        tenths -= 600;
+        minutes += 1;
+    }
+    if minutes >= 60 {
+        minutes -= 60;
+        degrees += 1;
+    }
+    write!(
+        f,
+        "{}°{:02}'{:02}.{}\"{}",
+        degrees,
+        minutes,
+        tenths / 10,
+        tenths % 10,
+        hemisphere
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Latitude(pub f64);
+
+impl Latitude {
+    pub fn to_dms(&self) -> (u16, u8, f64, char) {
+        to_dms(self.0, 'N', 'S')
+    }
+}
+
+impl fmt::Display for Latitude {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_dms(f, self.to_dms())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Longitude(pub f64);
+
+impl Longitude {
+    pub fn to_dms(&self) -> (u16, u8, f64, char) {
+        to_dms(self.0, 'E', 'W')
+    }
+}
+
+impl fmt::Display for Longitude {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_dms(f, self.to_dms())
+    }
+}
@@ -0,0 +1,48 @@
+use crate::Position;
+
+// Geohash digits, base 32 without a, i, l and o
+const GEOHASH_ALPHABET: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+// 12 characters is 60 bits, about 4 cm, more than the f64 input needs
+pub const GEOHASH_MAX_PRECISION: usize = 12;
+
+impl Position {
+    /*
+        Geohash of precision characters (clamped to 1 ..= 12), e.g. u4pruydqqvj.
+
+        Each character halves the longitude / latitude range five times, alternating and
+        starting with longitude. 5 characters is a ~5 km cell, 8 about 38 x 19 m, 9 about
+        5 m. Positions sharing a prefix are in the same cell, which makes the hash usable
+        as a database index for spatial bucketing.
+    */
+    pub fn to_geohash(&self, precision: usize) -> String {
+        let precision = precision.clamp(1, GEOHASH_MAX_PRECISION);
+        let mut lat_range = (-90.0, 90.0);
+        let mut lon_range = (-180.0, 180.0);
+        let lat = self.lat.clamp(-90.0, 90.0);
+        let lon = (self.lon + 180.0).rem_euclid(360.0) - 180.0;
+
+        let mut hash = String::with_capacity(precision);
+        let mut even_bit = true;
+        for _ in 0..precision {
+            let mut index = 0;
+            for _ in 0..5 {
+                let (range, value) = if even_bit {
+                    (&mut lon_range, lon)
+                } else {
+                    (&mut lat_range, lat)
+                };
+                let mid = (range.0 + range.1) / 2.0;
+                index <<= 1;
+                if value >= mid {
+                    index |= 1;
+                    range.0 = mid;
+                } else {
+                    range.1 = mid;
+                }
+                even_bit = !even_bit;
+            }
+            hash.push(GEOHASH_ALPHABET[index] as char);
+        }
+        hash
+    }
+}
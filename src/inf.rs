@@ -0,0 +1,117 @@
+use std::fmt;
+
+pub const UBX_CLASS_INF: u8 = 0x04;
+
+// Severity of a UBX-INF message, its message id
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum InfLevel {
+    Error = 0x00,
+    Warning = 0x01,
+    Notice = 0x02,
+    Test = 0x03,
+    Debug = 0x04,
+}
+
+impl InfLevel {
+    pub fn from_id(id: u8) -> Option<InfLevel> {
+        match id {
+            0x00 => Some(InfLevel::Error),
+            0x01 => Some(InfLevel::Warning),
+            0x02 => Some(InfLevel::Notice),
+            0x03 => Some(InfLevel::Test),
+            0x04 => Some(InfLevel::Debug),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for InfLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InfLevel::Error => write!(f, "ERROR"),
+            InfLevel::Warning => write!(f, "WARNING"),
+            InfLevel::Notice => write!(f, "NOTICE"),
+            InfLevel::Test => write!(f, "TEST"),
+            InfLevel::Debug => write!(f, "DEBUG"),
+        }
+    }
+}
+
+// Which INF levels a protocol outputs, infMsgMask of UBX-CFG-INF
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InfLevels {
+    pub error: bool,
+    pub warning: bool,
+    pub notice: bool,
+    pub test: bool,
+    pub debug: bool,
+}
+
+impl InfLevels {
+    pub const NONE: InfLevels = InfLevels {
+        error: false,
+        warning: false,
+        notice: false,
+        test: false,
+        debug: false,
+    };
+
+    // Factory default of the NMEA protocol
+    pub const ERRORS_WARNINGS_NOTICES: InfLevels = InfLevels {
+        error: true,
+        warning: true,
+        notice: true,
+        test: false,
+        debug: false,
+    };
+
+    pub const ALL: InfLevels = InfLevels {
+        error: true,
+        warning: true,
+        notice: true,
+        test: true,
+        debug: true,
+    };
+
+    // Bit n enables the level with message id n
+    pub fn mask(&self) -> u8 {
+        u8::from(self.error)
+            | u8::from(self.warning) << 1
+            | u8::from(self.notice) << 2
+            | u8::from(self.test) << 3
+            | u8::from(self.debug) << 4
+    }
+
+    pub fn from_mask(mask: u8) -> InfLevels {
+        InfLevels {
+            error: mask & 0x01 != 0,
+            warning: mask & 0x02 != 0,
+            notice: mask & 0x04 != 0,
+            test: mask & 0x08 != 0,
+            debug: mask & 0x10 != 0,
+        }
+    }
+}
+
+// UBX-INF-ERROR / WARNING / NOTICE / TEST / DEBUG (0x04 0x00 .. 0x04), a diagnostic string
+#[derive(Debug, Clone)]
+pub struct InfMessage {
+    pub level: InfLevel,
+    pub text: String,
+}
+
+impl fmt::Display for InfMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.level, self.text)
+    }
+}
+
+// id is the message id, the payload is the ASCII text (not zero terminated)
+pub fn parse_inf(id: u8, payload: &[u8]) -> Option<InfMessage> {
+    Some(InfMessage {
+        level: InfLevel::from_id(id)?,
+        text: String::from_utf8_lossy(payload)
+            .trim_end_matches(['\0', '\r', '\n'])
+            .to_string(),
+    })
+}
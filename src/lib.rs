@@ -26,9 +26,75 @@
     SOFTWARE.
 */
 
+#[cfg(feature = "serialport")]
 use serialport::SerialPort;
+use std::fmt;
+#[cfg(feature = "serialport")]
 use std::io;
 use std::time::Duration;
+#[cfg(feature = "serialport")]
+use std::time::Instant;
+
+#[macro_use]
+mod macros;
+
+mod aid;
+mod binlog;
+mod cfg;
+#[cfg(all(feature = "i2c", target_os = "linux"))]
+mod ddc;
+mod decode;
+mod error;
+mod export;
+mod filter;
+mod geo;
+mod geohash;
+mod geoid;
+mod inf;
+mod mon;
+mod nav;
+mod nmea;
+mod olc;
+mod parser;
+mod quality;
+#[cfg(feature = "serialport")]
+mod report;
+mod rxm;
+mod sim;
+mod stats;
+#[cfg(feature = "futures")]
+mod stream;
+mod time;
+
+pub use aid::*;
+pub use binlog::*;
+pub use cfg::*;
+#[cfg(all(feature = "i2c", target_os = "linux"))]
+pub use ddc::*;
+pub use decode::*;
+pub use error::*;
+pub use export::*;
+pub use filter::*;
+pub use geo::*;
+pub use geoid::*;
+pub use inf::*;
+pub use mon::*;
+pub use nav::*;
+pub use nmea::*;
+pub use parser::*;
+pub use quality::*;
+#[cfg(feature = "serialport")]
+pub use report::*;
+pub use rxm::*;
+pub use sim::*;
+pub use stats::*;
+#[cfg(feature = "futures")]
+pub use stream::*;
+pub use time::*;
+
+// Sync characters starting every UBX frame
+pub const UBX_SYNC_1: u8 = 0xB5;
+pub const UBX_SYNC_2: u8 = 0x62;
 
 #[derive(Debug)]
 pub struct UbxMessage {
@@ -37,6 +103,43 @@ pub struct UbxMessage {
     pub payload: Vec<u8>,
 }
 
+// Sync word, class, id and little endian payload length preceding every UBX payload
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UbxHeader {
+    pub class: u8,
+    pub id: u8,
+    pub length: u16,
+}
+
+impl UbxHeader {
+    pub const SIZE: usize = 6;
+
+    pub fn parse(data: &[u8]) -> Option<UbxHeader> {
+        if data.len() < Self::SIZE || data[0] != UBX_SYNC_1 || data[1] != UBX_SYNC_2 {
+            return None;
+        }
+
+        Some(UbxHeader {
+            class: data[2],
+            id: data[3],
+            length: u16::from_le_bytes([data[4], data[5]]),
+        })
+    }
+
+    // Whole frame: header, payload and checksum
+    pub fn frame_len(&self) -> usize {
+        8 + self.length as usize
+    }
+}
+
+// Total frame length (8 + payload length) declared by the first 6 bytes of a frame, or
+// None if they do not start with the sync word. Lets slow or packet oriented transports
+// read the header and then exactly the rest of the frame.
+pub fn frame_length_from_header(header: &[u8]) -> Option<usize> {
+    UbxHeader::parse(header).map(|h| h.frame_len())
+}
+
+#[derive(Debug, Clone)]
 pub struct Position {
     pub lat: f64,
     pub lon: f64,
@@ -81,59 +184,201 @@ pub fn svid_to_constellation(svid: u8) -> &'static str {
     }
 }
 
-pub fn parse_nav_svinfo(payload: &[u8]) {
-    if payload.len() < 8 {
-        println!("Payload too short for UBX-NAV-SVINFO");
-        return;
+// globalFlags chipGen field of NAV-SVINFO
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChipGeneration {
+    Antaris,
+    Ublox5,
+    Ublox6,
+    Ublox7,
+    Ublox8,
+    Unknown(u8),
+}
+
+impl ChipGeneration {
+    pub fn from_global_flags(global_flags: u8) -> ChipGeneration {
+        match global_flags & 0x07 {
+            0 => ChipGeneration::Antaris,
+            1 => ChipGeneration::Ublox5,
+            2 => ChipGeneration::Ublox6,
+            3 => ChipGeneration::Ublox7,
+            4 => ChipGeneration::Ublox8,
+            other => ChipGeneration::Unknown(other),
+        }
     }
+}
 
-    let num_ch = payload[4] as usize;
+// qualityInd of a NAV-SVINFO channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SvQuality {
+    // channel idle
+    NoSignal,
+    Searching,
+    Acquired,
+    // signal detected but unusable
+    Unusable,
+    // code locked and time synchronised
+    CodeLock,
+    // code and carrier locked and time synchronised (5, 6, 7)
+    CodeCarrierLocked,
+    Unknown(u8),
+}
 
-    println!("\n===== UBX-NAV-SVINFO =====");
-    println!("Number of channels: {}\n", num_ch);
+impl SvQuality {
+    pub fn from_u8(quality: u8) -> SvQuality {
+        match quality {
+            0 => SvQuality::NoSignal,
+            1 => SvQuality::Searching,
+            2 => SvQuality::Acquired,
+            3 => SvQuality::Unusable,
+            4 => SvQuality::CodeLock,
+            5..=7 => SvQuality::CodeCarrierLocked,
+            other => SvQuality::Unknown(other),
+        }
+    }
 
-    for i in 0..num_ch {
-        let base = 8 + i * 12;
-        if base + 12 > payload.len() {
-            println!("Truncated satellite block");
-            break;
+    // The satellite is actually being tracked, not just searched for
+    pub fn is_tracking(&self) -> bool {
+        matches!(self, SvQuality::CodeLock | SvQuality::CodeCarrierLocked)
+    }
+}
+
+impl fmt::Display for SvQuality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SvQuality::NoSignal => write!(f, "no signal"),
+            SvQuality::Searching => write!(f, "searching"),
+            SvQuality::Acquired => write!(f, "acquired"),
+            SvQuality::Unusable => write!(f, "unusable"),
+            SvQuality::CodeLock => write!(f, "code locked"),
+            SvQuality::CodeCarrierLocked => write!(f, "code and carrier locked"),
+            SvQuality::Unknown(q) => write!(f, "unknown ({})", q),
         }
+    }
+}
 
-        let chn = payload[base];
-        let svid = payload[base + 1];
-        let flags = payload[base + 2];
-        let quality = payload[base + 3];
-        let cno = payload[base + 4]; // carrier-to-noise ratio
-        let elev = payload[base + 5] as i8;
-        let azim = i16::from_le_bytes([payload[base + 6], payload[base + 7]]);
-        let pr_res = i32::from_le_bytes([
-            payload[base + 8],
-            payload[base + 9],
-            payload[base + 10],
-            payload[base + 11],
-        ]);
+// One channel block of UBX-NAV-SVINFO
+#[derive(Debug, Clone)]
+pub struct SvInfoChannel {
+    pub chn: u8,
+    pub svid: u8,
+    pub flags: u8,
+    pub quality: SvQuality,
+    // carrier-to-noise ratio, dBHz
+    pub cno: u8,
+    pub elevation: i8,
+    pub azimuth: i16,
+    // pseudo range residual, cm
+    pub pr_res: i32,
+}
+
+// UBX-NAV-SVINFO (0x01 0x30), space vehicle information
+#[derive(Debug, Clone)]
+pub struct NavSvInfo {
+    pub i_tow: u32,
+    pub chip_gen: ChipGeneration,
+    pub channels: Vec<SvInfoChannel>,
+}
 
-        println!("{}", svid_to_constellation(svid));
+pub fn parse_nav_svinfo(payload: &[u8]) -> Option<NavSvInfo> {
+    if payload.len() < 8 {
+        return None;
+    }
+
+    let num_ch = payload[4] as usize;
+
+    // A truncated trailing block is dropped, the complete ones are kept
+    let channels = payload[8..]
+        .chunks_exact(12)
+        .take(num_ch)
+        .map(|block| SvInfoChannel {
+            chn: block[0],
+            svid: block[1],
+            flags: block[2],
+            quality: SvQuality::from_u8(block[3]),
+            cno: block[4],
+            elevation: block[5] as i8,
+            azimuth: i16::from_le_bytes([block[6], block[7]]),
+            pr_res: i32::from_le_bytes([block[8], block[9], block[10], block[11]]),
+        })
+        .collect();
+
+    Some(NavSvInfo {
+        i_tow: u32::from_le_bytes([payload[0], payload[1], payload[2], payload[3]]),
+        chip_gen: ChipGeneration::from_global_flags(payload[5]),
+        channels,
+    })
+}
+
+pub fn print_nav_svinfo(svinfo: &NavSvInfo) {
+    println!("\n===== UBX-NAV-SVINFO =====");
+    println!("Chip generation: {:?}", svinfo.chip_gen);
+    println!("Number of channels: {}\n", svinfo.channels.len());
+
+    for ch in &svinfo.channels {
+        println!("{}", svid_to_constellation(ch.svid));
 
         println!(
             "CH: {:2} | SVID: {:3} | C/N₀: {:2} dBHz | Elv: {:3}° | Azim: {:4}° | Quality: {} | Flags: 0x{:02X} | PR Res: {}",
-            chn, svid, cno, elev, azim, quality, flags, pr_res
+            ch.chn, ch.svid, ch.cno, ch.elevation, ch.azimuth, ch.quality, ch.flags, ch.pr_res
         );
     }
 
     println!("======================================\n");
 }
 
+// Text layouts of format_position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PositionFormat {
+    // the multi line block print_position writes
+    Human,
+    // "51.4778000, -0.0014000"
+    DecimalDegrees,
+    // "51°28'40.1\"N 0°00'05.0\"W"
+    Dms,
+    // one JSON object, NaN accuracies as null
+    Json,
+}
+
+pub fn format_position(pos: &Position, fmt: PositionFormat) -> String {
+    match fmt {
+        PositionFormat::Human => format!(
+            "\n===== UBX NAV-POSLLH Parsed Position =====\n\
+             Latitude:  {:.7} °\n\
+             Longitude: {:.7} °\n\
+             Altitude:  {:.2} m (MSL)\n\
+             H-Acc:     {:.2} m\n\
+             V-Acc:     {:.2} m\n\
+             ========================================\n",
+            pos.lat, pos.lon, pos.height_msl, pos.horizontal_accuracy, pos.vertical_accuracy
+        ),
+        PositionFormat::DecimalDegrees => format!("{:.7}, {:.7}", pos.lat, pos.lon),
+        PositionFormat::Dms => format!("{} {}", pos.latitude(), pos.longitude()),
+        PositionFormat::Json => {
+            let number = |v: f64| {
+                if v.is_finite() {
+                    v.to_string()
+                } else {
+                    "null".to_string()
+                }
+            };
+            format!(
+                "{{\"lat\":{},\"lon\":{},\"height_msl\":{},\"horizontal_accuracy\":{},\"vertical_accuracy\":{}}}",
+                number(pos.lat),
+                number(pos.lon),
+                number(pos.height_msl),
+                number(pos.horizontal_accuracy),
+                number(pos.vertical_accuracy)
+            )
+        }
+    }
+}
+
 pub fn print_position(pos: &Position) {
-    println!("\n===== UBX NAV-POSLLH Parsed Position =====");
-    println!("Latitude:  {:.7} °", pos.lat);
-    println!("Longitude: {:.7} °", pos.lon);
-    println!("Altitude:  {:.2} m (MSL)", pos.height_msl);
-    println!("H-Acc:     {:.2} m", pos.horizontal_accuracy);
-    println!("V-Acc:     {:.2} m", pos.vertical_accuracy);
-    println!("========================================\n");
+    println!("{}", format_position(pos, PositionFormat::Human));
 }
 
+#[cfg(feature = "serialport")]
 pub fn open_serial(
     path: &str,
     baud_rate: u32,
@@ -150,6 +395,89 @@ pub fn open_serial(
     Ok(port)
 }
 
+// Opens the port and polls MON-VER, a port without a u-blox receiver (or at the wrong
+// baud rate) fails with UbxError::NotAUblox instead of timing out later on
+#[cfg(feature = "serialport")]
+pub fn open_and_verify(
+    path: &str,
+    baud_rate: u32,
+    timeout: Duration,
+) -> Result<Box<dyn SerialPort>, UbxError> {
+    let mut port = serialport::new(path, baud_rate)
+        .timeout(Duration::from_millis(200))
+        .open()
+        .map_err(io::Error::from)?;
+
+    let config = PollConfig {
+        retries: 1,
+        timeout,
+    };
+    match poll_message(&mut *port, 0x0A, 0x04, &[], &config) {
+        Ok(msg) if parse_mon_ver(&msg.payload).is_some() => Ok(port),
+        Ok(_) | Err(UbxError::Timeout) => Err(UbxError::NotAUblox(path.to_string())),
+        Err(e) => Err(e),
+    }
+}
+
+/*
+    Opens the port and waits up to window for the receiver to send anything.
+
+    On Linux ModemManager grabs freshly plugged /dev/ttyACM* devices: the open succeeds
+    but the receiver's output never arrives. A silent port, or one another process holds
+    exclusively (EBUSY), is reported as UbxError::PortBusy with a hint instead of a bare
+    timeout later on. A missing device is an I/O error of kind NotFound. The receiver must
+    have some periodic output enabled (the factory default NMEA set is enough).
+*/
+#[cfg(feature = "serialport")]
+pub fn diagnose_port(
+    path: &str,
+    baud_rate: u32,
+    window: Duration,
+) -> Result<Box<dyn SerialPort>, UbxError> {
+    let busy = || UbxError::PortBusy(path.to_string());
+    let port = match serialport::new(path, baud_rate)
+        .timeout(Duration::from_millis(200))
+        .open()
+    {
+        Ok(port) => port,
+        Err(e)
+            if e.kind() == serialport::ErrorKind::Unknown && open_is_busy(path, &e.description) =>
+        {
+            return Err(busy());
+        }
+        Err(e) if e.kind() == serialport::ErrorKind::NoDevice => {
+            return Err(io::Error::new(io::ErrorKind::NotFound, e.description).into());
+        }
+        Err(e) => return Err(io::Error::from(e).into()),
+    };
+
+    let deadline = Instant::now() + window;
+    while Instant::now() < deadline {
+        if bytes_available(&*port)? > 0 {
+            return Ok(port);
+        }
+        std::thread::sleep(IDLE_POLL_INTERVAL);
+    }
+
+    Err(busy())
+}
+
+/*
+    serialport has no kind for EBUSY (a tty another process opened with TIOCEXCL), on
+    Unix it comes as ErrorKind::Unknown with the nix errno text "Device or resource busy".
+    Opening the device again with std recovers the errno itself as ResourceBusy, the
+    text (any case) is the fallback.
+*/
+#[cfg(feature = "serialport")]
+fn open_is_busy(path: &str, description: &str) -> bool {
+    let reopened = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path);
+    matches!(reopened, Err(e) if e.kind() == io::ErrorKind::ResourceBusy)
+        || description.to_ascii_lowercase().contains("busy")
+}
+
 /*
     This is implementation of the Fletcher-8,
 
@@ -162,7 +490,7 @@ pub fn open_serial(
     It seems useful in low-power, low-bandwidth embedded systems or legacy protocols.
 */
 
-fn ubx_checksum(data: &[u8]) -> (u8, u8) {
+pub(crate) fn ubx_checksum(data: &[u8]) -> (u8, u8) {
     let mut ck_a = 0u8;
     let mut ck_b = 0u8;
     for byte in data {
@@ -172,49 +500,397 @@ fn ubx_checksum(data: &[u8]) -> (u8, u8) {
     (ck_a, ck_b)
 }
 
-// Ublox propietary protocol
+// Complete frame: sync, class, id, length, payload and checksum.
+// Panics if the payload exceeds the 16 bit length field (65535 bytes), callers taking
+// a payload from outside check the length first (see send_ubx_command).
+pub fn build_ubx_frame(class: u8, id: u8, payload: &[u8]) -> Vec<u8> {
+    let mut message = vec![0u8; 8 + payload.len()];
+    let len = build_ubx_frame_into(&mut message, class, id, payload)
+        .expect("UBX payload longer than 65535 bytes");
+    message.truncate(len);
+    message
+}
+
+// Allocation free variant of build_ubx_frame writing into buf. Returns the frame length,
+// or None if buf is too small (or the payload exceeds the 16 bit length field).
+pub fn build_ubx_frame_into(buf: &mut [u8], class: u8, id: u8, payload: &[u8]) -> Option<usize> {
+    let len = u16::try_from(payload.len()).ok()?;
+    let frame_len = 8 + payload.len();
+    if buf.len() < frame_len {
+        return None;
+    }
+
+    buf[0] = UBX_SYNC_1;
+    buf[1] = UBX_SYNC_2;
+    buf[2] = class;
+    buf[3] = id;
+    buf[4..6].copy_from_slice(&len.to_le_bytes());
+    buf[6..6 + payload.len()].copy_from_slice(payload);
+
+    let (ck_a, ck_b) = ubx_checksum(&buf[2..6 + payload.len()]);
+    buf[6 + payload.len()] = ck_a;
+    buf[7 + payload.len()] = ck_b;
+    Some(frame_len)
+}
+
+/*
+    Ublox propietary protocol
+
+    Writes the complete frame and returns its length. Short writes (some USB serial
+    adapters accept only part of a buffer) are continued, a write accepting nothing
+    fails with ErrorKind::WriteZero naming how much of the frame went out, so a truncated
+    frame is never reported as sent.
+*/
+#[cfg(feature = "serialport")]
 pub fn send_ubx_command(
     port: &mut dyn SerialPort,
     class: u8,
     id: u8,
     payload: &[u8],
-) -> io::Result<()> {
-    let mut message: Vec<u8> = vec![
-        0xB5,
-        0x62, // UBX sync chars
-        class,
-        id,
-        (payload.len() & 0xFF) as u8,
-        ((payload.len() >> 8) & 0xFF) as u8,
-    ];
-
-    message.extend_from_slice(payload);
-
-    let (ck_a, ck_b) = ubx_checksum(&message[2..]);
-    message.push(ck_a);
-    message.push(ck_b);
-
-    port.write_all(&message)?;
+) -> io::Result<usize> {
+    if payload.len() > u16::MAX as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "UBX payload for 0x{:02X} 0x{:02X} is {} bytes, at most 65535 fit a frame",
+                class,
+                id,
+                payload.len()
+            ),
+        ));
+    }
+    let message = build_ubx_frame(class, id, payload);
+    let mut written = 0;
+    while written < message.len() {
+        match port.write(&message[written..]) {
+            Ok(0) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    format!(
+                        "UBX frame 0x{:02X} 0x{:02X} cut short, {} of {} bytes written",
+                        class,
+                        id,
+                        written,
+                        message.len()
+                    ),
+                ));
+            }
+            Ok(n) => written += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
     port.flush()?;
-    Ok(())
+    Ok(written)
+}
+
+/*
+    Forwards RTCM 2.3 correction frames (e.g. from an NTRIP client) to the receiver input.
+    The port's input protocol mask must include RTCM, NavStatus::is_differential() then
+    confirms the corrections are being used.
+*/
+#[cfg(feature = "serialport")]
+pub fn feed_rtcm(port: &mut dyn SerialPort, rtcm_frame: &[u8]) -> io::Result<()> {
+    port.write_all(rtcm_frame)?;
+    port.flush()
+}
+
+// Number of bytes waiting in the OS input buffer, a read of up to this many bytes does not
+// block. For event loops polling the receiver without a dedicated reader thread.
+#[cfg(feature = "serialport")]
+pub fn bytes_available(port: &dyn SerialPort) -> io::Result<usize> {
+    Ok(port.bytes_to_read()? as usize)
 }
 
+// Reads and discards everything pending in the OS input buffer without blocking, returns
+// the number of bytes dropped. Call before a poll so stale output doesn't confuse the parse.
+#[cfg(feature = "serialport")]
+pub fn flush_input(port: &mut dyn SerialPort) -> io::Result<usize> {
+    let mut buf = [0u8; 1024];
+    let mut discarded = 0;
+
+    loop {
+        let pending = bytes_available(port)?.min(buf.len());
+        if pending == 0 {
+            return Ok(discarded);
+        }
+
+        let n = port.read(&mut buf[..pending])?;
+        if n == 0 {
+            return Ok(discarded);
+        }
+        discarded += n;
+    }
+}
+
+// Large enough for NAV-SAT with every channel in use
+pub const DEFAULT_READ_CAPACITY: usize = 4096;
+
+#[cfg(feature = "serialport")]
 pub fn read_ubx_response(port: &mut dyn SerialPort) -> Option<UbxMessage> {
+    read_ubx_message(port, DEFAULT_READ_CAPACITY).ok()
+}
+
+/*
+    Reads one frame starting at the first byte received, into a heap buffer of capacity
+    bytes. A frame split over several reads is completed with further reads. The header
+    is read first and then exactly the rest of the frame, bytes of the next frame are
+    left in the port.
+
+    Returns UbxError::Truncated if the header declares a frame larger than capacity,
+    instead of silently parsing a clipped payload.
+*/
+#[cfg(feature = "serialport")]
+pub fn read_ubx_message(
+    port: &mut dyn SerialPort,
+    capacity: usize,
+) -> Result<UbxMessage, UbxError> {
+    let mut buf = vec![0u8; capacity];
+    let mut filled = 0;
+
+    loop {
+        let mut wanted = UbxHeader::SIZE.min(capacity);
+        if let Some(frame_len) = frame_length_from_header(&buf[..filled]) {
+            if frame_len > capacity {
+                return Err(UbxError::Truncated {
+                    declared: frame_len,
+                    capacity,
+                });
+            }
+            if filled >= frame_len {
+                break;
+            }
+            wanted = frame_len;
+        } else if filled >= UbxHeader::SIZE
+            || filled == capacity
+            || buf[..filled.min(2)] != [UBX_SYNC_1, UBX_SYNC_2][..filled.min(2)]
+        {
+            break;
+        }
+
+        let n = match port.read(&mut buf[filled..wanted]) {
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => return Err(UbxError::Timeout),
+            Err(e) => return Err(e.into()),
+        };
+        if n == 0 {
+            break;
+        }
+        filled += n;
+    }
+
+    parse_ubx_message(&buf[..filled]).ok_or(UbxError::InvalidFrame)
+}
+
+// Reads until a frame with the given class / id arrives or the timeout expires. Frames
+// of other types received meanwhile are discarded.
+#[cfg(feature = "serialport")]
+pub fn wait_for_message(
+    port: &mut dyn SerialPort,
+    class: u8,
+    id: u8,
+    timeout: Duration,
+) -> Option<UbxMessage> {
+    wait_for_matching(port, timeout, |msg| msg.class == class && msg.id == id)
+}
+
+/*
+    Waits for the ACK-ACK (true) / ACK-NAK (false) of the message class / id, other
+    acknowledgements and frames are discarded. No answer within timeout is
+    UbxError::Timeout.
+
+    For confirming hand built frames:
+
+    send_ubx_command(&mut *port, 0x06, 0x08, &payload)?;
+    if !wait_for_ack(&mut *port, 0x06, 0x08, DEFAULT_POLL_TIMEOUT)? { ... }
+*/
+#[cfg(feature = "serialport")]
+pub fn wait_for_ack(
+    port: &mut dyn SerialPort,
+    class: u8,
+    id: u8,
+    timeout: Duration,
+) -> Result<bool, UbxError> {
+    wait_for_ack_of(port, class, Some(id), timeout).map(|(_, accepted)| accepted)
+}
+
+// wait_for_ack for message id, or any message of class if None. Returns the id
+// acknowledged and whether it was accepted.
+#[cfg(feature = "serialport")]
+pub(crate) fn wait_for_ack_of(
+    port: &mut dyn SerialPort,
+    class: u8,
+    id: Option<u8>,
+    timeout: Duration,
+) -> Result<(u8, bool), UbxError> {
+    let ack = wait_for_matching(port, timeout, |msg| {
+        msg.class == UBX_CLASS_ACK
+            && msg.payload.len() >= 2
+            && msg.payload[0] == class
+            && id.is_none_or(|id| msg.payload[1] == id)
+    })
+    .ok_or(UbxError::Timeout)?;
+
+    Ok((ack.payload[1], ack.id == UBX_ID_ACK_ACK))
+}
+
+// Pause between checks for pending input while waiting for a frame
+#[cfg(feature = "serialport")]
+const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+// Reads until a frame accepted by matches arrives or the timeout expires. Only bytes
+// already pending are read, so the wait never blocks past the deadline on the port's own
+// read timeout.
+#[cfg(feature = "serialport")]
+pub fn wait_for_matching<F>(
+    port: &mut dyn SerialPort,
+    timeout: Duration,
+    matches: F,
+) -> Option<UbxMessage>
+where
+    F: FnMut(&UbxMessage) -> bool,
+{
+    wait_for_matching_with(port, &mut UbxParser::new(), timeout, matches)
+}
+
+// wait_for_matching feeding parser, whatever is left in it (part of a frame still being
+// received) is kept for the next call
+#[cfg(feature = "serialport")]
+fn wait_for_matching_with<F>(
+    port: &mut dyn SerialPort,
+    parser: &mut UbxParser,
+    timeout: Duration,
+    mut matches: F,
+) -> Option<UbxMessage>
+where
+    F: FnMut(&UbxMessage) -> bool,
+{
+    let deadline = Instant::now() + timeout;
     let mut buf = [0u8; 1024];
-    match port.read(&mut buf) {
-        Ok(n) => parse_ubx_message(&buf[..n]),
-        Err(_) => None,
+
+    while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+        let pending = match bytes_available(port) {
+            Ok(pending) => pending.min(buf.len()),
+            Err(_) => return None,
+        };
+        if pending == 0 {
+            std::thread::sleep(remaining.min(IDLE_POLL_INTERVAL));
+            continue;
+        }
+
+        match port.read(&mut buf[..pending]) {
+            Ok(n) => parser.push(&buf[..n]),
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => continue,
+            Err(_) => return None,
+        }
+
+        while let Some(msg) = parser.next_message() {
+            if matches(&msg) {
+                return Some(msg);
+            }
+        }
     }
+
+    None
 }
 
-fn parse_ubx_message(data: &[u8]) -> Option<UbxMessage> {
-    if data.len() < 8 || data[0] != 0xB5 || data[1] != 0x62 {
-        return None;
+pub const DEFAULT_POLL_RETRIES: u8 = 10;
+pub const DEFAULT_POLL_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/*
+    Retry policy for poll_message.
+
+    retries: number of times the poll request is sent, default DEFAULT_POLL_RETRIES (10).
+    timeout: how long to wait for the answer after each send, default DEFAULT_POLL_TIMEOUT (1 s).
+
+    Interactive tools want a few short attempts, unattended loggers on a busy link are
+    better off with longer timeouts.
+*/
+#[derive(Debug, Clone, Copy)]
+pub struct PollConfig {
+    pub retries: u8,
+    pub timeout: Duration,
+}
+
+impl Default for PollConfig {
+    fn default() -> Self {
+        PollConfig {
+            retries: DEFAULT_POLL_RETRIES,
+            timeout: DEFAULT_POLL_TIMEOUT,
+        }
+    }
+}
+
+/*
+    Sends the poll request and waits for the matching class / id, retrying per config.
+    Returns UbxError::Timeout once all retries went unanswered, or the last write error if
+    the request could never be sent.
+
+    A retry is only used up by a window in which nothing matching arrived. If a frame is
+    still being received when the window ends (slow baud rate, long NAV-SAT), the wait is
+    extended by one more window without sending again.
+*/
+#[cfg(feature = "serialport")]
+pub fn poll_message(
+    port: &mut dyn SerialPort,
+    class: u8,
+    id: u8,
+    payload: &[u8],
+    config: &PollConfig,
+) -> Result<UbxMessage, UbxError> {
+    poll_message_expecting(port, class, id, payload, (class, id), config)
+}
+
+/*
+    Like poll_message, for requests not answered by an echo of their own class / id.
+
+    e.g. CFG-CFG (save) is only answered by ACK-ACK:
+        poll_message_expecting(port, 0x06, 0x09, &payload, (0x05, 0x01), &config)
+
+    When the expected response is ACK-ACK / ACK-NAK, only an acknowledgement for this
+    request's class / id is accepted.
+*/
+#[cfg(feature = "serialport")]
+pub fn poll_message_expecting(
+    port: &mut dyn SerialPort,
+    class: u8,
+    id: u8,
+    payload: &[u8],
+    response: (u8, u8),
+    config: &PollConfig,
+) -> Result<UbxMessage, UbxError> {
+    let matches = |msg: &UbxMessage| {
+        (msg.class, msg.id) == response
+            && (msg.class != UBX_CLASS_ACK || msg.payload.get(0..2) == Some(&[class, id][..]))
+    };
+    let mut last_err = UbxError::Timeout;
+    let mut parser = UbxParser::new();
+
+    for _ in 0..config.retries {
+        if let Err(e) = send_ubx_command(port, class, id, payload) {
+            last_err = e.into();
+            continue;
+        }
+        if let Some(response) = wait_for_matching_with(port, &mut parser, config.timeout, matches) {
+            return Ok(response);
+        }
+        // timed out mid-frame, the answer may be the frame still arriving
+        if parser.buffered() > 0
+            && let Some(response) =
+                wait_for_matching_with(port, &mut parser, config.timeout, matches)
+        {
+            return Ok(response);
+        }
+        last_err = UbxError::Timeout;
     }
 
-    let class = data[2];
-    let id = data[3];
-    let len = u16::from_le_bytes([data[4], data[5]]) as usize;
+    Err(last_err)
+}
+
+#[cfg(feature = "serialport")]
+fn parse_ubx_message(data: &[u8]) -> Option<UbxMessage> {
+    let header = UbxHeader::parse(data)?;
+    let len = header.length as usize;
     if data.len() < 8 + len {
         return None;
     }
@@ -225,7 +901,11 @@ fn parse_ubx_message(data: &[u8]) -> Option<UbxMessage> {
 
     let (calc_a, calc_b) = ubx_checksum(&data[2..6 + len]);
     if ck_a == calc_a && ck_b == calc_b {
-        Some(UbxMessage { class, id, payload })
+        Some(UbxMessage {
+            class: header.class,
+            id: header.id,
+            payload,
+        })
     } else {
         None
     }
@@ -0,0 +1,88 @@
+/*
+    Declares a fixed layout UBX message: the struct, its class / id, the minimum payload
+    length and a parse function with the length guard, from a list of fields.
+
+    Each field gives its Rust type, the little endian wire type and byte offset, and
+    optionally a scale applied as f64 (the field type must then be f64):
+
+    ubx_message! {
+        // UBX-NAV-DOP (0x01 0x04)
+        pub struct NavDop(0x01, 0x04) {
+            pub i_tow: u32 = u32 @ 0,
+            pub gdop: f64 = u16 @ 4 * 0.01,
+        }
+    }
+
+    generates NavDop::CLASS, NavDop::ID, NavDop::LEN (6 here, from the furthest field)
+    and NavDop::parse(payload) -> Option<NavDop>, returning None for short payloads.
+
+    Messages ending in reserved bytes can give the full length as a third argument,
+    struct NavAopStatus(0x01, 0x60, 20), LEN is then the larger of the two.
+*/
+#[macro_export]
+macro_rules! ubx_message {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident($class:expr, $id:expr $(, $min_len:expr)?) {
+            $(
+                $(#[$field_meta:meta])*
+                $field_vis:vis $field:ident : $out:ty = $raw:ident @ $offset:literal $(* $scale:expr)?
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone)]
+        $vis struct $name {
+            $(
+                $(#[$field_meta])*
+                $field_vis $field: $out,
+            )*
+        }
+
+        impl $name {
+            pub const CLASS: u8 = $class;
+            pub const ID: u8 = $id;
+            pub const LEN: usize = {
+                let mut len: usize = $crate::ubx_message!(@min_len $($min_len)?);
+                $(
+                    let end = $offset + ::core::mem::size_of::<$raw>();
+                    if end > len {
+                        len = end;
+                    }
+                )*
+                len
+            };
+
+            pub fn parse(payload: &[u8]) -> Option<$name> {
+                if payload.len() < Self::LEN {
+                    return None;
+                }
+
+                Some($name {
+                    $(
+                        $field: {
+                            let size = ::core::mem::size_of::<$raw>();
+                            let raw = <$raw>::from_le_bytes(
+                                payload[$offset..$offset + size].try_into().ok()?,
+                            );
+                            $crate::ubx_message!(@value raw, $out $(, $scale)?)
+                        },
+                    )*
+                })
+            }
+        }
+    };
+
+    (@min_len $len:expr) => {
+        $len
+    };
+    (@min_len) => {
+        0
+    };
+    (@value $raw:ident, $out:ty, $scale:expr) => {
+        $raw as f64 * $scale
+    };
+    (@value $raw:ident, $out:ty) => {
+        $raw as $out
+    };
+}
@@ -1,52 +1,8 @@
 use serialport::SerialPort;
-use ublox7::{open_serial, read_ubx_response, send_ubx_command};
-
-const MAX_RETRY: u8 = 10;
-
-fn parse_nav_svinfo(payload: &[u8]) {
-    if payload.len() < 8 {
-        println!("Payload too short for UBX-NAV-SVINFO");
-        return;
-    }
-
-    let num_ch = payload[4] as usize;
-
-    println!("\n===== UBX-NAV-SVINFO =====");
-    println!("Number of channels: {}\n", num_ch);
-
-    #[cfg(debug_assertions)]
-    for i in 0..num_ch {
-        let base = 8 + i * 12;
-        if base + 12 > payload.len() {
-            println!("Truncated satellite block");
-            break;
-        }
-
-        let chn = payload[base];
-        let svid = payload[base + 1];
-        let flags = payload[base + 2];
-        let quality = payload[base + 3];
-        let cno = payload[base + 4]; // carrier-to-noise ratio
-        let elev = payload[base + 5] as i8;
-        let azim = i16::from_le_bytes([payload[base + 6], payload[base + 7]]);
-        let pr_res = i32::from_le_bytes([
-            payload[base + 8],
-            payload[base + 9],
-            payload[base + 10],
-            payload[base + 11],
-        ]);
-
-        println!(
-            "CH: {:2} | SVID: {:3} | C/N₀: {:2} dBHz | Elv: {:3}° | Azim: {:4}° | Quality: {} | Flags: 0x{:02X} | PR Res: {}",
-            chn, svid, cno, elev, azim, quality, flags, pr_res
-        );
-
-        let svid = payload[base + 1];
-        println!("Channel: {} {}", i, ublox7::svid_to_constellation(svid));
-    }
-
-    println!("======================================\n");
-}
+use ublox7::{
+    PollConfig, open_serial, parse_nav_sat, parse_nav_svinfo, poll_message, print_nav_svinfo,
+    read_ubx_response, send_ubx_command,
+};
 
 pub fn get_sat_info(port: &mut dyn SerialPort) -> Result<(), Box<dyn std::error::Error>> {
     use std::thread::sleep;
@@ -58,34 +14,24 @@ pub fn get_sat_info(port: &mut dyn SerialPort) -> Result<(), Box<dyn std::error:
 
     if let Some(response) = read_ubx_response(port) {
         if response.class == 0x01 && response.id == 0x35 {
-            println!("Raw NAV-SAT payload: {:?}", response.payload);
-            if response.payload.len() < 8 {
-                println!("Not enough data received for NAV-SAT header.");
+            let Some(nav_sat) = parse_nav_sat(&response.payload) else {
+                println!("Not enough data received for NAV-SAT.");
                 return Ok(());
-            }
-
-            let num_svs = response.payload[5] as usize;
-            println!("Detected {} satellites:\n", num_svs);
-
-            for i in 0..num_svs {
-                let base = 8 + i * 12;
-                if base + 12 > response.payload.len() {
-                    println!("Truncated satellite block at index {}", i);
-                    break;
-                }
-
-                let gnss_id = response.payload[base];
-                let sv_id = response.payload[base + 1];
-                let cno = response.payload[base + 2];
-                let flags = response.payload[base + 3];
-                let azimuth =
-                    i16::from_le_bytes([response.payload[base + 4], response.payload[base + 5]]);
-                let elevation = response.payload[base + 6] as i8;
-                let orbit_source = response.payload[base + 7];
+            };
+            println!("Detected {} satellites:\n", nav_sat.satellites.len());
 
+            for sv in &nav_sat.satellites {
+                let flags = sv.flags();
                 println!(
-                    "SV {:02}: GNSS={} | C/N₀={} dBHz | Az={}° | El={}° | Flags=0x{:02X} | OrbitSrc={}",
-                    sv_id, gnss_id, cno, azimuth, elevation, flags, orbit_source
+                    "SV {:02}: GNSS={} | C/N₀={} dBHz | Az={}° | El={}° | Used={} | Health={:?} | OrbitSrc={:?}",
+                    sv.sv_id,
+                    sv.gnss_id,
+                    sv.cno,
+                    sv.azimuth,
+                    sv.elevation,
+                    flags.sv_used,
+                    flags.health,
+                    flags.orbit_source
                 );
             }
         } else {
@@ -103,33 +49,6 @@ pub fn get_sat_info(port: &mut dyn SerialPort) -> Result<(), Box<dyn std::error:
     Ok(())
 }
 
-fn get_ublox7_message(
-    mut port: Box<dyn SerialPort>,
-    class: u8,
-    id: u8,
-    payload: [u8; 0],
-) -> Result<(Box<dyn SerialPort>, ublox7::UbxMessage), Box<dyn SerialPort>> {
-    for i in 1..=MAX_RETRY {
-        if let Err(e) = send_ubx_command(&mut *port, class, id, &payload) {
-            println!("Error: {}", e);
-            continue;
-        }
-        if let Some(response) = read_ubx_response(&mut *port) {
-            println!(
-                "Got response from Ublox7, Attempt: {}/{} Class: {}, Id: {}",
-                i, MAX_RETRY, response.class, response.id
-            );
-            // return
-            if response.class == class && response.id == id {
-                return Ok((port, response));
-            }
-        }
-    }
-
-    println!("Error: no response after: {} retries", MAX_RETRY);
-    Err(port)
-}
-
 fn parse_ublox7_data(ubx_message: ublox7::UbxMessage) {
     if ubx_message.class == 0x01 && ubx_message.id == 0x02 && ubx_message.payload.len() >= 28 {
         let payload = &ubx_message.payload;
@@ -159,25 +78,35 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut port = open_serial("/dev/ttyACM0", 19200)?;
 
     // Attempting to retrieve vehichle (satelitte) information such as: {gps, glonass, beiduo}
-    if let Some(sat_response_svinfo) = read_ubx_response(&mut *port) {
-        if sat_response_svinfo.class == 0x01 && sat_response_svinfo.id == 0x30 {
-            parse_nav_svinfo(&sat_response_svinfo.payload);
+    if let Some(sat_response_svinfo) = read_ubx_response(&mut *port)
+        && sat_response_svinfo.class == 0x01
+        && sat_response_svinfo.id == 0x30
+    {
+        match parse_nav_svinfo(&sat_response_svinfo.payload) {
+            Some(svinfo) => print_nav_svinfo(&svinfo),
+            None => println!("Payload too short for UBX-NAV-SVINFO"),
         }
     }
 
     let class = 0x01;
     let id = 0x02;
-    let payload: [u8; 0] = []; // set to 0
+    let config = PollConfig::default();
 
     println!("Command sent. Waiting for UBX response...");
 
-    let ublox7_response = get_ublox7_message(port, class, id, payload);
-    match ublox7_response {
+    match poll_message(&mut *port, class, id, &[], &config) {
         Ok(ubx_message) => {
-            parse_ublox7_data(ubx_message.1);
+            println!(
+                "Got response from Ublox7, Class: {}, Id: {}",
+                ubx_message.class, ubx_message.id
+            );
+            parse_ublox7_data(ubx_message);
         }
         Err(e) => {
-            println!("Error, failed communicating with Ublox7: {:?}", e);
+            println!(
+                "Error, failed communicating with Ublox7 after {} retries: {}",
+                config.retries, e
+            );
         }
     }
 
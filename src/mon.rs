@@ -0,0 +1,303 @@
+use crate::UbxError;
+#[cfg(feature = "serialport")]
+use crate::{DEFAULT_POLL_TIMEOUT, PollConfig, poll_message};
+#[cfg(feature = "serialport")]
+use serialport::SerialPort;
+use std::fmt;
+
+// UBX-MON-VER (0x0A 0x04), receiver / software version
+#[derive(Debug, Clone)]
+pub struct MonVer {
+    pub sw_version: String,
+    pub hw_version: String,
+    // e.g. "PROTVER 14.00", "GPS;SBAS;GLO;QZSS"
+    pub extensions: Vec<String>,
+}
+
+// Fixed width, zero terminated strings
+fn parse_ch(field: &[u8]) -> String {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).trim().to_string()
+}
+
+impl MonVer {
+    // Protocol version from the "PROTVER 14.00" extension ("PROTVER=18.00" on later
+    // firmware), None if there is none
+    pub fn protocol_version(&self) -> Option<f32> {
+        self.extensions.iter().find_map(|ext| {
+            let version = ext.strip_prefix("PROTVER")?;
+            version.trim_start_matches([' ', '=']).trim().parse().ok()
+        })
+    }
+}
+
+pub fn parse_mon_ver(payload: &[u8]) -> Option<MonVer> {
+    if payload.len() < 40 {
+        return None;
+    }
+
+    let extensions = payload[40..]
+        .chunks_exact(30)
+        .map(parse_ch)
+        .filter(|ext| !ext.is_empty())
+        .collect();
+
+    Some(MonVer {
+        sw_version: parse_ch(&payload[0..30]),
+        hw_version: parse_ch(&payload[30..40]),
+        extensions,
+    })
+}
+
+/*
+    Polls MON-VER for the UBX protocol version, e.g. 14.0 for u-blox 7 firmware 1.00, so
+    callers can gate messages by firmware (NAV-PVT needs 14, NAV-SAT 15 ...).
+
+    Fails with UbxError::NoProtocolVersion if the receiver answers without a PROTVER line.
+*/
+#[cfg(feature = "serialport")]
+pub fn detect_protocol_version(port: &mut dyn SerialPort) -> Result<f32, UbxError> {
+    let config = PollConfig {
+        retries: 2,
+        timeout: DEFAULT_POLL_TIMEOUT,
+    };
+    let msg = poll_message(port, 0x0A, 0x04, &[], &config)?;
+    parse_mon_ver(&msg.payload)
+        .ok_or(UbxError::InvalidFrame)?
+        .protocol_version()
+        .ok_or(UbxError::NoProtocolVersion)
+}
+
+// aStatus of MON-HW, reported by the antenna supervisor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AntennaStatus {
+    Init,
+    DontKnow,
+    Ok,
+    Short,
+    Open,
+    Unknown(u8),
+}
+
+impl AntennaStatus {
+    pub fn from_u8(status: u8) -> AntennaStatus {
+        match status {
+            0 => AntennaStatus::Init,
+            1 => AntennaStatus::DontKnow,
+            2 => AntennaStatus::Ok,
+            3 => AntennaStatus::Short,
+            4 => AntennaStatus::Open,
+            other => AntennaStatus::Unknown(other),
+        }
+    }
+}
+
+impl fmt::Display for AntennaStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AntennaStatus::Init => write!(f, "initializing"),
+            AntennaStatus::DontKnow => write!(f, "unknown"),
+            AntennaStatus::Ok => write!(f, "OK"),
+            AntennaStatus::Short => write!(f, "short circuit"),
+            AntennaStatus::Open => write!(f, "open circuit"),
+            AntennaStatus::Unknown(s) => write!(f, "unknown ({})", s),
+        }
+    }
+}
+
+// aPower of MON-HW, state of the active antenna supply
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AntennaPower {
+    Off,
+    On,
+    DontKnow,
+    Unknown(u8),
+}
+
+impl AntennaPower {
+    pub fn from_u8(power: u8) -> AntennaPower {
+        match power {
+            0 => AntennaPower::Off,
+            1 => AntennaPower::On,
+            2 => AntennaPower::DontKnow,
+            other => AntennaPower::Unknown(other),
+        }
+    }
+}
+
+ubx_message! {
+    // UBX-MON-HW (0x0A 0x09), hardware status, 60 bytes on u-blox 7
+    pub struct MonHw(0x0A, 0x09, 60) {
+        pub pin_sel: u32 = u32 @ 0,
+        pub pin_bank: u32 = u32 @ 4,
+        pub pin_dir: u32 = u32 @ 8,
+        pub pin_val: u32 = u32 @ 12,
+        // noise level as measured by the GPS core
+        pub noise_per_ms: u16 = u16 @ 16,
+        // AGC monitor, 0 .. 8191
+        pub agc_cnt: u16 = u16 @ 18,
+        pub a_status: u8 = u8 @ 20,
+        pub a_power: u8 = u8 @ 21,
+        pub flags: u8 = u8 @ 22,
+        pub used_mask: u32 = u32 @ 24,
+        // CW jamming indicator, 0 no CW jamming .. 255 strong CW jamming
+        pub jam_ind: u8 = u8 @ 45,
+    }
+}
+
+impl MonHw {
+    pub fn antenna_status(&self) -> AntennaStatus {
+        AntennaStatus::from_u8(self.a_status)
+    }
+
+    pub fn antenna_power(&self) -> AntennaPower {
+        AntennaPower::from_u8(self.a_power)
+    }
+}
+
+pub fn parse_mon_hw(payload: &[u8]) -> Option<MonHw> {
+    MonHw::parse(payload)
+}
+
+/*
+    Receiver temperature.
+
+    The u-blox 7 has no message reporting a die or board temperature, MON-HW only carries
+    noise level, AGC, antenna and jamming status. This always returns
+    UbxError::Unsupported so callers logging environmental data can tell "not available
+    on this hardware" apart from a failed read.
+*/
+pub fn parse_temperature(_payload: &[u8]) -> Result<f64, UbxError> {
+    Err(UbxError::Unsupported(
+        "temperature is not reported by u-blox 7 receivers",
+    ))
+}
+
+/*
+    Unique chip ID from UBX-SEC-UNIQID (0x27 0x03), as a hex string.
+
+    SEC-UNIQID appeared with protocol 15 (u-blox M8), u-blox 7 firmware does not answer
+    it and MON-VER carries no per-device serial, so on a 7-series this returns
+    UbxError::Unsupported. Modules with newer firmware get their 40 bit chip ID.
+*/
+#[cfg(feature = "serialport")]
+pub fn get_chip_id(port: &mut dyn SerialPort) -> Result<String, UbxError> {
+    let config = PollConfig {
+        retries: 2,
+        timeout: DEFAULT_POLL_TIMEOUT,
+    };
+
+    match poll_message(port, 0x27, 0x03, &[], &config) {
+        Ok(msg) if msg.payload.len() >= 9 => Ok(msg.payload[4..9]
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect()),
+        Ok(_) => Err(UbxError::InvalidFrame),
+        Err(UbxError::Timeout) => Err(UbxError::Unsupported(
+            "SEC-UNIQID is not available, u-blox 7 firmware exposes no unique chip ID",
+        )),
+        Err(e) => Err(e),
+    }
+}
+
+// Buffer targets of MON-RXBUF / MON-TXBUF, index into the per-target arrays
+pub const BUFFER_TARGETS: [&str; 6] = ["DDC", "UART1", "UART2", "USB", "SPI", "reserved"];
+
+// Per-target buffer usage of MON-RXBUF / MON-TXBUF
+#[derive(Debug, Clone)]
+pub struct BufferUsage {
+    // bytes pending
+    pub pending: [u16; 6],
+    // current usage, %
+    pub usage: [u8; 6],
+    // peak usage, %
+    pub peak_usage: [u8; 6],
+}
+
+fn parse_buffer_usage(payload: &[u8]) -> BufferUsage {
+    let mut pending = [0u16; 6];
+    for (p, bytes) in pending.iter_mut().zip(payload[0..12].chunks_exact(2)) {
+        *p = u16::from_le_bytes([bytes[0], bytes[1]]);
+    }
+
+    BufferUsage {
+        pending,
+        usage: payload[12..18].try_into().unwrap_or_default(),
+        peak_usage: payload[18..24].try_into().unwrap_or_default(),
+    }
+}
+
+// UBX-MON-RXBUF (0x0A 0x07), receive buffer status
+pub fn parse_mon_rxbuf(payload: &[u8]) -> Option<BufferUsage> {
+    if payload.len() < 24 {
+        return None;
+    }
+    Some(parse_buffer_usage(payload))
+}
+
+// UBX-MON-TXBUF (0x0A 0x08), transmit buffer status
+#[derive(Debug, Clone)]
+pub struct MonTxBuf {
+    pub targets: BufferUsage,
+    // usage / peak usage of the transmitter buffer shared by all targets, %
+    pub total_usage: u8,
+    pub total_peak_usage: u8,
+    // bits 0..5 buffer limit of the target reached, 6 memory error, 7 allocation error
+    pub errors: u8,
+}
+
+impl MonTxBuf {
+    // Output for target was dropped because its buffer limit was reached
+    pub fn limit_reached(&self, target: usize) -> bool {
+        target < 6 && self.errors & (1 << target) != 0
+    }
+
+    pub fn memory_error(&self) -> bool {
+        self.errors & 0xC0 != 0
+    }
+}
+
+pub fn parse_mon_txbuf(payload: &[u8]) -> Option<MonTxBuf> {
+    if payload.len() < 28 {
+        return None;
+    }
+
+    Some(MonTxBuf {
+        targets: parse_buffer_usage(payload),
+        total_usage: payload[24],
+        total_peak_usage: payload[25],
+        errors: payload[26],
+    })
+}
+
+// Receive and transmit buffer state, from buffer_health
+#[derive(Debug, Clone)]
+pub struct BufferHealth {
+    pub rx: BufferUsage,
+    pub tx: MonTxBuf,
+}
+
+impl BufferHealth {
+    // The receiver dropped output: a TX buffer limit was hit or the buffers ran out of
+    // memory. The host is not reading fast enough (or the baud rate is too low for the
+    // enabled messages).
+    pub fn is_dropping(&self) -> bool {
+        self.tx.errors != 0
+    }
+}
+
+// Polls MON-RXBUF and MON-TXBUF
+#[cfg(feature = "serialport")]
+pub fn buffer_health(port: &mut dyn SerialPort) -> Result<BufferHealth, UbxError> {
+    let config = PollConfig {
+        retries: 2,
+        timeout: DEFAULT_POLL_TIMEOUT,
+    };
+
+    let rx = poll_message(port, 0x0A, 0x07, &[], &config)?;
+    let tx = poll_message(port, 0x0A, 0x08, &[], &config)?;
+    Ok(BufferHealth {
+        rx: parse_mon_rxbuf(&rx.payload).ok_or(UbxError::InvalidFrame)?,
+        tx: parse_mon_txbuf(&tx.payload).ok_or(UbxError::InvalidFrame)?,
+    })
+}
@@ -0,0 +1,923 @@
+use crate::{Bearing, Position, UbxError, correct_week_rollover, gps_time_to_utc, utc_from_fields};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+ubx_message! {
+    // UBX-NAV-AOPSTATUS (0x01 0x60), AssistNow Autonomous status
+    pub struct NavAopStatus(0x01, 0x60, 20) {
+        pub i_tow: u32 = u32 @ 0,
+        pub aop_cfg: u8 = u8 @ 4,
+        pub status: u8 = u8 @ 5,
+        // Bitmask of GPS SVs (bit n = PRN n + 1) with AOP data available
+        pub avail_gps: u32 = u32 @ 8,
+    }
+}
+
+impl NavAopStatus {
+    pub fn aop_enabled(&self) -> bool {
+        self.aop_cfg & 0x01 != 0
+    }
+
+    // A nonzero status means AOP is currently computing orbits, power cycling now loses
+    // the work in progress.
+    pub fn is_running(&self) -> bool {
+        self.status != 0
+    }
+}
+
+pub fn parse_nav_aopstatus(payload: &[u8]) -> Option<NavAopStatus> {
+    NavAopStatus::parse(payload)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FixType {
+    NoFix,
+    DeadReckoning,
+    Fix2D,
+    Fix3D,
+    GpsDeadReckoning,
+    TimeOnly,
+    Unknown(u8),
+}
+
+impl FixType {
+    pub fn from_u8(value: u8) -> FixType {
+        match value {
+            0x00 => FixType::NoFix,
+            0x01 => FixType::DeadReckoning,
+            0x02 => FixType::Fix2D,
+            0x03 => FixType::Fix3D,
+            0x04 => FixType::GpsDeadReckoning,
+            0x05 => FixType::TimeOnly,
+            other => FixType::Unknown(other),
+        }
+    }
+}
+
+impl fmt::Display for FixType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FixType::NoFix => write!(f, "No fix"),
+            FixType::DeadReckoning => write!(f, "Dead reckoning"),
+            FixType::Fix2D => write!(f, "2D"),
+            FixType::Fix3D => write!(f, "3D"),
+            FixType::GpsDeadReckoning => write!(f, "GPS + dead reckoning"),
+            FixType::TimeOnly => write!(f, "Time only"),
+            FixType::Unknown(v) => write!(f, "Unknown (0x{:02X})", v),
+        }
+    }
+}
+
+// UBX-NAV-STATUS (0x01 0x03), receiver navigation status
+#[derive(Debug, Clone)]
+pub struct NavStatus {
+    pub i_tow: u32,
+    pub gps_fix: FixType,
+    pub flags: u8,
+    pub fix_stat: u8,
+    pub flags2: u8,
+    // Time to first fix, ms
+    pub ttff: u32,
+    // Milliseconds since startup / reset
+    pub msss: u32,
+}
+
+impl NavStatus {
+    // Fix is within the configured DOP and accuracy masks
+    pub fn gps_fix_ok(&self) -> bool {
+        self.flags & 0x01 != 0
+    }
+
+    // Differential corrections (DGPS / SBAS) were applied to the solution
+    pub fn is_differential(&self) -> bool {
+        self.flags & 0x02 != 0
+    }
+
+    pub fn week_valid(&self) -> bool {
+        self.flags & 0x04 != 0
+    }
+
+    pub fn tow_valid(&self) -> bool {
+        self.flags & 0x08 != 0
+    }
+}
+
+pub fn parse_nav_status(payload: &[u8]) -> Option<NavStatus> {
+    if payload.len() < 16 {
+        return None;
+    }
+
+    Some(NavStatus {
+        i_tow: u32::from_le_bytes([payload[0], payload[1], payload[2], payload[3]]),
+        gps_fix: FixType::from_u8(payload[4]),
+        flags: payload[5],
+        fix_stat: payload[6],
+        flags2: payload[7],
+        ttff: u32::from_le_bytes([payload[8], payload[9], payload[10], payload[11]]),
+        msss: u32::from_le_bytes([payload[12], payload[13], payload[14], payload[15]]),
+    })
+}
+
+ubx_message! {
+    // UBX-NAV-DOP (0x01 0x04), dilution of precision, already scaled by 0.01
+    pub struct NavDop(0x01, 0x04) {
+        pub i_tow: u32 = u32 @ 0,
+        pub gdop: f64 = u16 @ 4 * 0.01,
+        pub pdop: f64 = u16 @ 6 * 0.01,
+        pub tdop: f64 = u16 @ 8 * 0.01,
+        pub vdop: f64 = u16 @ 10 * 0.01,
+        pub hdop: f64 = u16 @ 12 * 0.01,
+        pub ndop: f64 = u16 @ 14 * 0.01,
+        pub edop: f64 = u16 @ 16 * 0.01,
+    }
+}
+
+pub fn parse_nav_dop(payload: &[u8]) -> Option<NavDop> {
+    NavDop::parse(payload)
+}
+
+ubx_message! {
+    // UBX-NAV-CLOCK (0x01 0x22), receiver clock solution relative to GPS time
+    pub struct NavClock(0x01, 0x22) {
+        pub i_tow: u32 = u32 @ 0,
+        // clock bias, ns
+        pub clk_b: i32 = i32 @ 4,
+        // clock drift, ns/s
+        pub clk_d: i32 = i32 @ 8,
+        // time accuracy estimate, ns
+        pub t_acc: u32 = u32 @ 12,
+        // frequency accuracy estimate, ps/s
+        pub f_acc: u32 = u32 @ 16,
+    }
+}
+
+impl NavClock {
+    /*
+        Offset of a GNSS time scale from the solution's time base, ns.
+
+        u-blox 7 solutions are referenced to GPS time (clk_b is the receiver clock bias
+        against it), so GPS is 0. The 7-series does not report GPS-GLONASS or other
+        inter-system offsets in any message, those return UbxError::Unsupported.
+    */
+    pub fn inter_system_bias(&self, system: GnssId) -> Result<f64, UbxError> {
+        match system {
+            GnssId::Gps => Ok(0.0),
+            _ => Err(UbxError::Unsupported(
+                "inter-system time offsets are not reported by u-blox 7 receivers",
+            )),
+        }
+    }
+}
+
+pub fn parse_nav_clock(payload: &[u8]) -> Option<NavClock> {
+    NavClock::parse(payload)
+}
+
+// One satellite block of UBX-NAV-SAT
+#[derive(Debug, Clone)]
+pub struct SatInfo {
+    pub gnss_id: GnssId,
+    pub sv_id: u8,
+    // carrier-to-noise ratio, dBHz
+    pub cno: u8,
+    pub elevation: i8,
+    pub azimuth: i16,
+    // pseudo range residual, m
+    pub pr_res: f64,
+    pub flags: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SvHealth {
+    Unknown,
+    Healthy,
+    Unhealthy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrbitSource {
+    None,
+    Ephemeris,
+    Almanac,
+    AssistNowOffline,
+    AssistNowAutonomous,
+    Other(u8),
+}
+
+// Decoded NAV-SAT per satellite flags word
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SatFlags {
+    // signal quality indicator, 0 no signal .. 4 code locked, 5-7 code and carrier locked
+    pub quality_ind: u8,
+    pub sv_used: bool,
+    pub health: SvHealth,
+    pub diff_corr: bool,
+    pub smoothed: bool,
+    pub orbit_source: OrbitSource,
+    pub eph_avail: bool,
+    pub alm_avail: bool,
+    pub ano_avail: bool,
+    pub aop_avail: bool,
+    pub sbas_corr_used: bool,
+    pub rtcm_corr_used: bool,
+    pub pr_corr_used: bool,
+    pub cr_corr_used: bool,
+    pub do_corr_used: bool,
+}
+
+impl SatFlags {
+    pub fn from_u32(flags: u32) -> SatFlags {
+        let bit = |n: u32| flags & (1 << n) != 0;
+
+        SatFlags {
+            quality_ind: (flags & 0x07) as u8,
+            sv_used: bit(3),
+            health: match (flags >> 4) & 0x03 {
+                1 => SvHealth::Healthy,
+                2 => SvHealth::Unhealthy,
+                _ => SvHealth::Unknown,
+            },
+            diff_corr: bit(6),
+            smoothed: bit(7),
+            orbit_source: match (flags >> 8) & 0x07 {
+                0 => OrbitSource::None,
+                1 => OrbitSource::Ephemeris,
+                2 => OrbitSource::Almanac,
+                3 => OrbitSource::AssistNowOffline,
+                4 => OrbitSource::AssistNowAutonomous,
+                other => OrbitSource::Other(other as u8),
+            },
+            eph_avail: bit(11),
+            alm_avail: bit(12),
+            ano_avail: bit(13),
+            aop_avail: bit(14),
+            sbas_corr_used: bit(16),
+            rtcm_corr_used: bit(17),
+            pr_corr_used: bit(20),
+            cr_corr_used: bit(21),
+            do_corr_used: bit(22),
+        }
+    }
+}
+
+// UBX-NAV-SAT (0x01 0x35), satellite information
+#[derive(Debug, Clone)]
+pub struct NavSat {
+    pub i_tow: u32,
+    pub version: u8,
+    pub satellites: Vec<SatInfo>,
+}
+
+// GNSS system identifier (gnssId) of NAV-SAT and CFG-GNSS. Not the same numbering as
+// the NAV-SVINFO svid, which encodes the system in the satellite number instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum GnssId {
+    Gps,
+    Sbas,
+    Galileo,
+    BeiDou,
+    Imes,
+    Qzss,
+    Glonass,
+    Unknown(u8),
+}
+
+impl GnssId {
+    pub fn from_u8(gnss_id: u8) -> GnssId {
+        match gnss_id {
+            0 => GnssId::Gps,
+            1 => GnssId::Sbas,
+            2 => GnssId::Galileo,
+            3 => GnssId::BeiDou,
+            4 => GnssId::Imes,
+            5 => GnssId::Qzss,
+            6 => GnssId::Glonass,
+            other => GnssId::Unknown(other),
+        }
+    }
+
+    pub fn to_u8(self) -> u8 {
+        match self {
+            GnssId::Gps => 0,
+            GnssId::Sbas => 1,
+            GnssId::Galileo => 2,
+            GnssId::BeiDou => 3,
+            GnssId::Imes => 4,
+            GnssId::Qzss => 5,
+            GnssId::Glonass => 6,
+            GnssId::Unknown(id) => id,
+        }
+    }
+}
+
+impl fmt::Display for GnssId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GnssId::Gps => write!(f, "GPS"),
+            GnssId::Sbas => write!(f, "SBAS"),
+            GnssId::Galileo => write!(f, "Galileo"),
+            GnssId::BeiDou => write!(f, "BeiDou"),
+            GnssId::Imes => write!(f, "IMES"),
+            GnssId::Qzss => write!(f, "QZSS"),
+            GnssId::Glonass => write!(f, "GLONASS"),
+            GnssId::Unknown(id) => write!(f, "unknown ({})", id),
+        }
+    }
+}
+
+impl SatInfo {
+    pub fn flags(&self) -> SatFlags {
+        SatFlags::from_u32(self.flags)
+    }
+
+    pub fn constellation(&self) -> GnssId {
+        self.gnss_id
+    }
+
+    // Receiving a signal (C/N0 above 0)
+    pub fn is_tracked(&self) -> bool {
+        self.cno > 0
+    }
+}
+
+impl NavSat {
+    /*
+        Copy keeping only the satellites received at min_cno dBHz or better, so every
+        accessor applies the same noise threshold:
+
+        nav_sat.filtered(30).used_count()
+        nav_sat.filtered(30).constellation_counts()
+
+        Around 30 dBHz separates usable signals from noise, below 20 a satellite is
+        barely acquired.
+    */
+    pub fn filtered(&self, min_cno: u8) -> NavSat {
+        NavSat {
+            i_tow: self.i_tow,
+            version: self.version,
+            satellites: self
+                .satellites
+                .iter()
+                .filter(|sv| sv.cno >= min_cno)
+                .cloned()
+                .collect(),
+        }
+    }
+
+    // Satellites with svUsed set, i.e. contributing to the navigation solution
+    pub fn used_count(&self) -> usize {
+        self.satellites
+            .iter()
+            .filter(|sv| sv.flags().sv_used)
+            .count()
+    }
+
+    // All satellite blocks in the message, tracked or not
+    pub fn visible_count(&self) -> usize {
+        self.satellites.len()
+    }
+
+    // e.g. "7/12 used"
+    pub fn summary(&self) -> String {
+        format!("{}/{} used", self.used_count(), self.visible_count())
+    }
+
+    // Distinct constellations among the tracked satellites, e.g. to check that a CFG-GNSS
+    // change took effect
+    pub fn constellations_present(&self) -> HashSet<GnssId> {
+        self.satellites
+            .iter()
+            .filter(|sv| sv.is_tracked())
+            .map(|sv| sv.constellation())
+            .collect()
+    }
+
+    // Number of tracked satellites per constellation
+    pub fn constellation_counts(&self) -> HashMap<GnssId, usize> {
+        let mut counts = HashMap::new();
+        for sv in self.satellites.iter().filter(|sv| sv.is_tracked()) {
+            *counts.entry(sv.constellation()).or_insert(0) += 1;
+        }
+        counts
+    }
+}
+
+pub fn parse_nav_sat(payload: &[u8]) -> Option<NavSat> {
+    if payload.len() < 8 {
+        return None;
+    }
+
+    let num_svs = payload[5] as usize;
+    if payload.len() < 8 + num_svs * 12 {
+        return None;
+    }
+
+    let satellites = payload[8..8 + num_svs * 12]
+        .chunks_exact(12)
+        .map(|sv| SatInfo {
+            gnss_id: GnssId::from_u8(sv[0]),
+            sv_id: sv[1],
+            cno: sv[2],
+            elevation: sv[3] as i8,
+            azimuth: i16::from_le_bytes([sv[4], sv[5]]),
+            pr_res: i16::from_le_bytes([sv[6], sv[7]]) as f64 * 0.1,
+            flags: u32::from_le_bytes([sv[8], sv[9], sv[10], sv[11]]),
+        })
+        .collect();
+
+    Some(NavSat {
+        i_tow: u32::from_le_bytes([payload[0], payload[1], payload[2], payload[3]]),
+        version: payload[4],
+        satellites,
+    })
+}
+
+// One satellite block of UBX-NAV-DGPS
+#[derive(Debug, Clone)]
+pub struct DgpsCorrection {
+    pub sv_id: u8,
+    // bits 0..3 channel, bit 4 corrections used
+    pub flags: u8,
+    // age of the correction, ms
+    pub age: u16,
+    // pseudo range correction, m
+    pub prc: f32,
+    // pseudo range rate correction, m/s
+    pub prrc: f32,
+}
+
+impl DgpsCorrection {
+    pub fn used(&self) -> bool {
+        self.flags & 0x10 != 0
+    }
+
+    pub fn channel(&self) -> u8 {
+        self.flags & 0x0F
+    }
+}
+
+/*
+    UBX-NAV-DGPS (0x01 0x31), differential corrections applied to the solution.
+
+    The u-blox 7 block carries no IODC / IODE, which ephemeris a correction refers to is
+    not reported.
+*/
+#[derive(Debug, Clone)]
+pub struct NavDgps {
+    pub i_tow: u32,
+    // age of the newest correction data, ms
+    pub age: i32,
+    // DGPS base station ID and health
+    pub base_id: i16,
+    pub base_health: i16,
+    // 0 none, 1 PR + PRR correction
+    pub status: u8,
+    pub corrections: Vec<DgpsCorrection>,
+}
+
+impl NavDgps {
+    pub fn corrections_applied(&self) -> bool {
+        self.status != 0 && self.corrections.iter().any(|c| c.used())
+    }
+}
+
+pub fn parse_nav_dgps(payload: &[u8]) -> Option<NavDgps> {
+    if payload.len() < 16 {
+        return None;
+    }
+
+    let num_ch = payload[12] as usize;
+    if payload.len() < 16 + num_ch * 12 {
+        return None;
+    }
+
+    let corrections = payload[16..16 + num_ch * 12]
+        .chunks_exact(12)
+        .map(|sv| DgpsCorrection {
+            sv_id: sv[0],
+            flags: sv[1],
+            age: u16::from_le_bytes([sv[2], sv[3]]),
+            prc: f32::from_le_bytes([sv[4], sv[5], sv[6], sv[7]]),
+            prrc: f32::from_le_bytes([sv[8], sv[9], sv[10], sv[11]]),
+        })
+        .collect();
+
+    Some(NavDgps {
+        i_tow: u32::from_le_bytes([payload[0], payload[1], payload[2], payload[3]]),
+        age: i32::from_le_bytes([payload[4], payload[5], payload[6], payload[7]]),
+        base_id: i16::from_le_bytes([payload[8], payload[9]]),
+        base_health: i16::from_le_bytes([payload[10], payload[11]]),
+        status: payload[13],
+        corrections,
+    })
+}
+
+// UBX-NAV-TIMEGPS (0x01 0x20), GPS time solution
+#[derive(Debug, Clone)]
+pub struct NavTimeGps {
+    pub i_tow: u32,
+    // fractional part of iTOW, ns (range +/- 500000)
+    pub f_tow: i32,
+    pub week: i16,
+    // GPS - UTC leap seconds
+    pub leap_s: i8,
+    pub valid: u8,
+    // time accuracy estimate, ns
+    pub t_acc: u32,
+}
+
+impl NavTimeGps {
+    pub fn tow_valid(&self) -> bool {
+        self.valid & 0x01 != 0
+    }
+
+    pub fn week_valid(&self) -> bool {
+        self.valid & 0x02 != 0
+    }
+
+    pub fn leap_s_valid(&self) -> bool {
+        self.valid & 0x04 != 0
+    }
+
+    // UTC from week / iTOW / leap seconds. Pass a rollover reference to apply
+    // correct_week_rollover for firmware predating the 2019 week rollover.
+    pub fn utc(&self, rollover_reference: Option<SystemTime>) -> SystemTime {
+        let mut utc = gps_time_to_utc(self.week.max(0) as u16, self.i_tow, self.leap_s);
+        let f_tow = Duration::from_nanos(self.f_tow.unsigned_abs() as u64);
+        if self.f_tow >= 0 {
+            utc += f_tow;
+        } else {
+            utc -= f_tow;
+        }
+        match rollover_reference {
+            Some(reference) => correct_week_rollover(utc, reference),
+            None => utc,
+        }
+    }
+}
+
+pub fn parse_nav_timegps(payload: &[u8]) -> Option<NavTimeGps> {
+    if payload.len() < 16 {
+        return None;
+    }
+
+    Some(NavTimeGps {
+        i_tow: u32::from_le_bytes([payload[0], payload[1], payload[2], payload[3]]),
+        f_tow: i32::from_le_bytes([payload[4], payload[5], payload[6], payload[7]]),
+        week: i16::from_le_bytes([payload[8], payload[9]]),
+        leap_s: payload[10] as i8,
+        valid: payload[11],
+        t_acc: u32::from_le_bytes([payload[12], payload[13], payload[14], payload[15]]),
+    })
+}
+
+ubx_message! {
+    /*
+        UBX-NAV-TIMELS (0x01 0x26), leap second state and the next announced leap second.
+
+        Added with protocol 15 (u-blox M8), u-blox 7 firmware neither outputs nor answers a
+        poll for it. On a 7-series the only leap second information is the current GPS -
+        UTC offset, NavTimeGps::leap_s (valid once leap_s_valid()), upcoming events are not
+        exposed.
+    */
+    pub struct NavTimeLs(0x01, 0x26, 24) {
+        pub i_tow: u32 = u32 @ 0,
+        // 0 default, 1 GPS - GLONASS derived, 2 GPS, 3 SBAS, 4 BeiDou, 5 Galileo,
+        // 6 aided, 7 configured, 255 unknown
+        pub src_of_current: u8 = u8 @ 8,
+        // GPS - UTC leap seconds
+        pub current_leap_s: i8 = i8 @ 9,
+        // 0 no source, 2 GPS, 3 SBAS, 4 BeiDou, 5 Galileo, 6 GLONASS
+        pub src_of_ls_change: u8 = u8 @ 10,
+        // upcoming change of the leap seconds, -1 / 0 / +1
+        pub ls_change: i8 = i8 @ 11,
+        // seconds until the next leap second event (or since the last one if negative)
+        pub time_to_lsevent: i32 = i32 @ 12,
+        // GPS week and day of week (Sunday = 1) of the next leap second event
+        pub ls_event_week: u16 = u16 @ 16,
+        pub ls_event_day: u16 = u16 @ 18,
+        pub valid: u8 = u8 @ 23,
+    }
+}
+
+impl NavTimeLs {
+    pub fn current_leap_s_valid(&self) -> bool {
+        self.valid & 0x01 != 0
+    }
+
+    pub fn time_to_lsevent_valid(&self) -> bool {
+        self.valid & 0x02 != 0
+    }
+
+    // Start (GPS midnight, as UTC) of the day the next leap second is inserted at the end
+    // of, None if no event is known (day 0) or the day of week is out of range
+    pub fn date_of_lsevent(&self) -> Option<SystemTime> {
+        if !self.time_to_lsevent_valid() || !(1..=7).contains(&self.ls_event_day) {
+            return None;
+        }
+        let day_start_ms = u32::try_from((self.ls_event_day as u64 - 1) * 86_400_000).ok()?;
+        Some(gps_time_to_utc(
+            self.ls_event_week,
+            day_start_ms,
+            self.current_leap_s,
+        ))
+    }
+}
+
+pub fn parse_nav_timels(payload: &[u8]) -> Option<NavTimeLs> {
+    NavTimeLs::parse(payload)
+}
+
+// Start of the first GLONASS four year interval (N4 = 1), 1996-01-01 00:00 UTC
+const GLONASS_EPOCH_UNIX_S: u64 = 820_454_400;
+// GLONASS time is UTC(SU), Moscow time
+const GLONASS_UTC_OFFSET_S: u64 = 3 * 3600;
+
+ubx_message! {
+    /*
+        UBX-NAV-TIMEGLO (0x01 0x23), GLONASS time solution.
+
+        Added with protocol 15 (u-blox M8) like NAV-TIMELS, u-blox 7 firmware does not
+        output it. There is no leap second field: GLONASS time is kept on UTC(SU) + 3 h and
+        inserts leap seconds together with UTC, the GPS - UTC offset is
+        NavTimeGps::leap_s.
+    */
+    pub struct NavTimeGlo(0x01, 0x23, 20) {
+        pub i_tow: u32 = u32 @ 0,
+        // GLONASS time of day, s
+        pub tod: u32 = u32 @ 4,
+        // fraction of tod, ns (range -1e9 .. 1e9)
+        pub f_tod: i32 = i32 @ 8,
+        // day within the four year interval, 1 .. 1461
+        pub n_t: u16 = u16 @ 12,
+        // four year interval since 1996, 1 for 1996 - 1999
+        pub n_4: u8 = u8 @ 14,
+        pub valid: u8 = u8 @ 15,
+        // time accuracy estimate, ns
+        pub t_acc: u32 = u32 @ 16,
+    }
+}
+
+impl NavTimeGlo {
+    pub fn tod_valid(&self) -> bool {
+        self.valid & 0x01 != 0
+    }
+
+    pub fn date_valid(&self) -> bool {
+        self.valid & 0x02 != 0
+    }
+
+    // The GLONASS time as UTC, None unless both time of day and date are valid
+    pub fn utc(&self) -> Option<SystemTime> {
+        if !self.tod_valid() || !self.date_valid() || self.n_4 == 0 || self.n_t == 0 {
+            return None;
+        }
+        let days = (self.n_4 as u64 - 1) * 1461 + (self.n_t as u64 - 1);
+        let secs = (GLONASS_EPOCH_UNIX_S + days * 86_400 + self.tod as u64)
+            .checked_sub(GLONASS_UTC_OFFSET_S)?;
+        let t = UNIX_EPOCH + Duration::from_secs(secs);
+        Some(if self.f_tod >= 0 {
+            t + Duration::from_nanos(self.f_tod as u64)
+        } else {
+            t - Duration::from_nanos(self.f_tod.unsigned_abs() as u64)
+        })
+    }
+}
+
+pub fn parse_nav_timeglo(payload: &[u8]) -> Option<NavTimeGlo> {
+    NavTimeGlo::parse(payload)
+}
+
+// UBX-NAV-TIMEUTC (0x01 0x21), UTC time solution
+#[derive(Debug, Clone)]
+pub struct NavTimeUtc {
+    pub i_tow: u32,
+    // time accuracy estimate, ns
+    pub t_acc: u32,
+    // fraction of second, ns (range -1e9 .. 1e9)
+    pub nano: i32,
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub min: u8,
+    pub sec: u8,
+    pub valid: u8,
+}
+
+impl NavTimeUtc {
+    pub fn tow_valid(&self) -> bool {
+        self.valid & 0x01 != 0
+    }
+
+    pub fn week_valid(&self) -> bool {
+        self.valid & 0x02 != 0
+    }
+
+    pub fn utc_valid(&self) -> bool {
+        self.valid & 0x04 != 0
+    }
+
+    // Pass a rollover reference to apply correct_week_rollover for firmware predating the
+    // 2019 week rollover.
+    pub fn utc(&self, rollover_reference: Option<SystemTime>) -> SystemTime {
+        let utc = utc_from_fields(
+            self.year, self.month, self.day, self.hour, self.min, self.sec, self.nano,
+        );
+        match rollover_reference {
+            Some(reference) => correct_week_rollover(utc, reference),
+            None => utc,
+        }
+    }
+}
+
+pub fn parse_nav_timeutc(payload: &[u8]) -> Option<NavTimeUtc> {
+    if payload.len() < 20 {
+        return None;
+    }
+
+    Some(NavTimeUtc {
+        i_tow: u32::from_le_bytes([payload[0], payload[1], payload[2], payload[3]]),
+        t_acc: u32::from_le_bytes([payload[4], payload[5], payload[6], payload[7]]),
+        nano: i32::from_le_bytes([payload[8], payload[9], payload[10], payload[11]]),
+        year: u16::from_le_bytes([payload[12], payload[13]]),
+        month: payload[14],
+        day: payload[15],
+        hour: payload[16],
+        min: payload[17],
+        sec: payload[18],
+        valid: payload[19],
+    })
+}
+
+// UBX-NAV-VELNED (0x01 0x12), velocity in the local NED frame, SI units
+#[derive(Debug, Clone)]
+pub struct Velocity {
+    pub i_tow: u32,
+    // m/s
+    pub vel_n: f64,
+    pub vel_e: f64,
+    pub vel_d: f64,
+    // 3D speed, m/s
+    pub speed: f64,
+    // 2D ground speed, m/s
+    pub ground_speed: f64,
+    // heading of motion
+    pub heading: Bearing,
+    // m/s
+    pub speed_accuracy: f64,
+    // degrees
+    pub heading_accuracy: f64,
+}
+
+pub fn parse_nav_velned(payload: &[u8]) -> Option<Velocity> {
+    if payload.len() < 36 {
+        return None;
+    }
+
+    let i4 = |o: usize| {
+        i32::from_le_bytes([payload[o], payload[o + 1], payload[o + 2], payload[o + 3]]) as f64
+    };
+    let u4 = |o: usize| {
+        u32::from_le_bytes([payload[o], payload[o + 1], payload[o + 2], payload[o + 3]]) as f64
+    };
+
+    Some(Velocity {
+        i_tow: u32::from_le_bytes([payload[0], payload[1], payload[2], payload[3]]),
+        vel_n: i4(4) / 100.0,
+        vel_e: i4(8) / 100.0,
+        vel_d: i4(12) / 100.0,
+        speed: u4(16) / 100.0,
+        ground_speed: u4(20) / 100.0,
+        heading: Bearing::new(i4(24) * 1e-5),
+        speed_accuracy: u4(28) / 100.0,
+        heading_accuracy: u4(32) * 1e-5,
+    })
+}
+
+// UBX-NAV-PVT (0x01 0x07), navigation position velocity time solution, SI units
+#[derive(Debug, Clone)]
+pub struct NavPvt {
+    pub i_tow: u32,
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub min: u8,
+    pub sec: u8,
+    pub valid: u8,
+    // time accuracy estimate, ns
+    pub t_acc: u32,
+    // fraction of second, ns
+    pub nano: i32,
+    pub fix_type: FixType,
+    pub flags: u8,
+    pub num_sv: u8,
+    pub lon: f64,
+    pub lat: f64,
+    // height above ellipsoid, m
+    pub height: f64,
+    pub height_msl: f64,
+    pub horizontal_accuracy: f64,
+    pub vertical_accuracy: f64,
+    // m/s
+    pub vel_n: f64,
+    pub vel_e: f64,
+    pub vel_d: f64,
+    pub ground_speed: f64,
+    // heading of motion
+    pub heading: Bearing,
+    // m/s
+    pub speed_accuracy: f64,
+    // degrees
+    pub heading_accuracy: f64,
+    pub pdop: f64,
+}
+
+impl NavPvt {
+    pub fn gnss_fix_ok(&self) -> bool {
+        self.flags & 0x01 != 0
+    }
+
+    // Differential corrections (DGPS / SBAS) were applied to the solution
+    pub fn is_differential(&self) -> bool {
+        self.flags & 0x02 != 0
+    }
+
+    pub fn utc(&self) -> SystemTime {
+        utc_from_fields(
+            self.year, self.month, self.day, self.hour, self.min, self.sec, self.nano,
+        )
+    }
+
+    // A 2D / 3D (or GPS + dead reckoning) fix with gnssFixOK set, so PVT alone can gate
+    // positions without also enabling NAV-STATUS. Time-only fixes carry no position.
+    pub fn is_valid_fix(&self) -> bool {
+        matches!(
+            self.fix_type,
+            FixType::Fix2D | FixType::Fix3D | FixType::GpsDeadReckoning
+        ) && self.gnss_fix_ok()
+    }
+
+    // The position, or None unless is_valid_fix()
+    pub fn position(&self) -> Option<Position> {
+        self.is_valid_fix().then(|| Position::from(self))
+    }
+}
+
+impl From<&NavPvt> for Position {
+    fn from(pvt: &NavPvt) -> Self {
+        Position {
+            lat: pvt.lat,
+            lon: pvt.lon,
+            height_msl: pvt.height_msl,
+            horizontal_accuracy: pvt.horizontal_accuracy,
+            vertical_accuracy: pvt.vertical_accuracy,
+        }
+    }
+}
+
+pub fn parse_nav_pvt(payload: &[u8]) -> Option<NavPvt> {
+    // 84 bytes on protocol 14 (u-blox 7), later generations append fields
+    if payload.len() < 84 {
+        return None;
+    }
+
+    let i4 = |o: usize| {
+        i32::from_le_bytes([payload[o], payload[o + 1], payload[o + 2], payload[o + 3]]) as f64
+    };
+    let u4 = |o: usize| {
+        u32::from_le_bytes([payload[o], payload[o + 1], payload[o + 2], payload[o + 3]]) as f64
+    };
+
+    Some(NavPvt {
+        i_tow: u32::from_le_bytes([payload[0], payload[1], payload[2], payload[3]]),
+        year: u16::from_le_bytes([payload[4], payload[5]]),
+        month: payload[6],
+        day: payload[7],
+        hour: payload[8],
+        min: payload[9],
+        sec: payload[10],
+        valid: payload[11],
+        t_acc: u32::from_le_bytes([payload[12], payload[13], payload[14], payload[15]]),
+        nano: i32::from_le_bytes([payload[16], payload[17], payload[18], payload[19]]),
+        fix_type: FixType::from_u8(payload[20]),
+        flags: payload[21],
+        num_sv: payload[23],
+        lon: i4(24) * 1e-7,
+        lat: i4(28) * 1e-7,
+        height: i4(32) / 1000.0,
+        height_msl: i4(36) / 1000.0,
+        horizontal_accuracy: u4(40) / 1000.0,
+        vertical_accuracy: u4(44) / 1000.0,
+        vel_n: i4(48) / 1000.0,
+        vel_e: i4(52) / 1000.0,
+        vel_d: i4(56) / 1000.0,
+        ground_speed: i4(60) / 1000.0,
+        heading: Bearing::new(i4(64) * 1e-5),
+        speed_accuracy: u4(68) / 1000.0,
+        heading_accuracy: u4(72) * 1e-5,
+        pdop: u16::from_le_bytes([payload[76], payload[77]]) as f64 * 0.01,
+    })
+}
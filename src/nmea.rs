@@ -0,0 +1,367 @@
+use crate::{Bearing, Position, geoid_separation, utc_fields};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, ErrorKind};
+use std::time::SystemTime;
+
+// XOR of every character between '$' and '*'
+pub fn nmea_checksum(body: &str) -> u8 {
+    body.bytes().fold(0, |acc, b| acc ^ b)
+}
+
+// A checksum-verified NMEA 0183 sentence split into its comma separated fields
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NmeaSentence {
+    // "GP", "GL", "GN" ...
+    pub talker: String,
+    // "GGA", "GSV", "RMC" ...
+    pub kind: String,
+    pub fields: Vec<String>,
+}
+
+impl NmeaSentence {
+    // Parses "$GPGSV,...*hh" (trailing CR / LF allowed). Returns None if the framing is
+    // wrong or the checksum does not match.
+    pub fn parse(line: &str) -> Option<NmeaSentence> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        let body = line.strip_prefix('$')?;
+        let (body, checksum) = body.rsplit_once('*')?;
+        if u8::from_str_radix(checksum, 16).ok()? != nmea_checksum(body) {
+            return None;
+        }
+
+        let mut parts = body.split(',');
+        let address = parts.next()?;
+        if address.len() < 5 || !address.is_ascii() {
+            return None;
+        }
+        let (talker, kind) = address.split_at(2);
+
+        Some(NmeaSentence {
+            talker: talker.to_string(),
+            kind: kind.to_string(),
+            fields: parts.map(str::to_string).collect(),
+        })
+    }
+
+    pub fn field(&self, index: usize) -> Option<&str> {
+        self.fields
+            .get(index)
+            .map(String::as_str)
+            .filter(|f| !f.is_empty())
+    }
+}
+
+// One satellite in view from a GSV sentence, empty fields are None
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NmeaSatellite {
+    // "GP", "GL" ... of the GSV group the satellite came from
+    pub talker: String,
+    pub prn: u16,
+    pub elevation: Option<i8>,
+    pub azimuth: Option<u16>,
+    // dBHz, None when not tracked
+    pub snr: Option<u8>,
+}
+
+struct GsvGroup {
+    total: u8,
+    parts: BTreeMap<u8, Vec<NmeaSatellite>>,
+}
+
+/*
+    Collects the 1-of-M .. M-of-M GSV sentences of one sky view.
+
+    Parts may arrive in any order, the group is returned once all M parts are present.
+    A group is abandoned (its parts dropped) when a part with a different total arrives
+    or a part number repeats, which is what a lost sentence looks like on the wire.
+    Groups are kept per talker, so GPGSV and GLGSV interleave correctly.
+*/
+#[derive(Default)]
+pub struct GsvAccumulator {
+    groups: HashMap<String, GsvGroup>,
+}
+
+impl GsvAccumulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Feeds one sentence, non GSV sentences are ignored. Returns the satellites of the
+    // group once it is complete.
+    pub fn push(&mut self, sentence: &NmeaSentence) -> Option<Vec<NmeaSatellite>> {
+        if sentence.kind != "GSV" {
+            return None;
+        }
+
+        let total: u8 = sentence.field(0)?.parse().ok()?;
+        let number: u8 = sentence.field(1)?.parse().ok()?;
+        if total == 0 || number == 0 || number > total {
+            return None;
+        }
+
+        let satellites = sentence
+            .fields
+            .get(3..)
+            .unwrap_or_default()
+            .chunks(4)
+            .filter_map(|sv| {
+                let field = |i: usize| sv.get(i).map(String::as_str).filter(|f| !f.is_empty());
+                Some(NmeaSatellite {
+                    talker: sentence.talker.clone(),
+                    prn: field(0)?.parse().ok()?,
+                    elevation: field(1).and_then(|v| v.parse().ok()),
+                    azimuth: field(2).and_then(|v| v.parse().ok()),
+                    snr: field(3).and_then(|v| v.parse().ok()),
+                })
+            })
+            .collect();
+
+        let group = self
+            .groups
+            .entry(sentence.talker.clone())
+            .or_insert_with(|| GsvGroup {
+                total,
+                parts: BTreeMap::new(),
+            });
+        if group.total != total || group.parts.contains_key(&number) {
+            group.total = total;
+            group.parts.clear();
+        }
+        group.parts.insert(number, satellites);
+
+        if group.parts.len() == total as usize {
+            let group = self.groups.remove(&sentence.talker)?;
+            return Some(group.parts.into_values().flatten().collect());
+        }
+        None
+    }
+}
+
+// NMEA 0183 caps sentences at 82 characters, allow some slack for proprietary ones
+pub(crate) const MAX_SENTENCE_LEN: usize = 256;
+
+/*
+    Streaming NMEA reader, the line based counterpart of UbxParser.
+
+    Bytes are pushed as they are read, next_sentence() returns every complete line
+    (CRLF or bare LF terminated) that parses and passes the checksum. Lines failing the
+    checksum, text before the '$' and runaway lines with no terminator are dropped.
+*/
+#[derive(Default)]
+pub struct NmeaReader {
+    buf: Vec<u8>,
+}
+
+impl NmeaReader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, data: &[u8]) {
+        self.buf.extend_from_slice(data);
+    }
+
+    pub fn next_sentence(&mut self) -> Option<NmeaSentence> {
+        loop {
+            let Some(end) = self.buf.iter().position(|&b| b == b'\n') else {
+                if self.buf.len() > MAX_SENTENCE_LEN {
+                    self.buf.clear();
+                }
+                return None;
+            };
+
+            let line: Vec<u8> = self.buf.drain(..=end).collect();
+            let Some(start) = line.iter().position(|&b| b == b'$') else {
+                continue;
+            };
+            if let Ok(text) = std::str::from_utf8(&line[start..])
+                && let Some(sentence) = NmeaSentence::parse(text)
+            {
+                return Some(sentence);
+            }
+        }
+    }
+}
+
+// ddmm.mmmm / dddmm.mmmm with its N/S / E/W indicator to signed degrees
+fn nmea_coordinate(value: &str, hemisphere: &str) -> Option<f64> {
+    let dot = value.find('.').unwrap_or(value.len());
+    if dot < 3 {
+        return None;
+    }
+    let degrees: f64 = value[..dot - 2].parse().ok()?;
+    let minutes: f64 = value[dot - 2..].parse().ok()?;
+    let coordinate = degrees + minutes / 60.0;
+
+    match hemisphere {
+        "N" | "E" => Some(coordinate),
+        "S" | "W" => Some(-coordinate),
+        _ => None,
+    }
+}
+
+impl NmeaSentence {
+    /*
+        Position of a GGA (with a fix) or RMC (status A) sentence, other sentences give
+        None. NMEA carries no accuracy estimates, horizontal / vertical accuracy are NaN,
+        as is the height of RMC which has no altitude field.
+    */
+    pub fn position(&self) -> Option<Position> {
+        let (lat, lon, height_msl) = match self.kind.as_str() {
+            "GGA" => {
+                if self.field(5)? == "0" {
+                    return None;
+                }
+                let height = self.field(8).and_then(|h| h.parse().ok());
+                (
+                    nmea_coordinate(self.field(1)?, self.field(2)?)?,
+                    nmea_coordinate(self.field(3)?, self.field(4)?)?,
+                    height.unwrap_or(f64::NAN),
+                )
+            }
+            "RMC" => {
+                if self.field(1)? != "A" {
+                    return None;
+                }
+                (
+                    nmea_coordinate(self.field(2)?, self.field(3)?)?,
+                    nmea_coordinate(self.field(4)?, self.field(5)?)?,
+                    f64::NAN,
+                )
+            }
+            _ => return None,
+        };
+
+        Some(Position {
+            lat,
+            lon,
+            height_msl,
+            horizontal_accuracy: f64::NAN,
+            vertical_accuracy: f64::NAN,
+        })
+    }
+}
+
+/*
+    Positions from an NMEA log or port, one per GGA / RMC fix, so NMEA input can feed the
+    same Position based code as UBX input. See NmeaSentence::position() for what is left
+    as NaN.
+
+    Receivers usually send GGA and RMC for every epoch, an RMC with the same time stamp
+    as the GGA just before it is skipped. Lines that do not parse are ignored, binary
+    (UBX) bytes before a sentence on the same line are dropped. Read timeouts between
+    epochs are skipped, iteration ends at the end of input or on any other read error.
+*/
+pub fn nmea_to_positions(mut reader: impl BufRead) -> impl Iterator<Item = Position> {
+    let mut last_gga_time: Option<String> = None;
+    let mut line = Vec::new();
+
+    std::iter::from_fn(move || {
+        loop {
+            match reader.read_until(b'\n', &mut line) {
+                Ok(0) if line.is_empty() => return None,
+                Ok(_) => {}
+                // Bytes read before the timeout stay in line, the rest follows
+                Err(e) if matches!(e.kind(), ErrorKind::TimedOut | ErrorKind::Interrupted) => {
+                    continue;
+                }
+                Err(_) => return None,
+            }
+
+            let text = String::from_utf8_lossy(&line);
+            let sentence = text
+                .rfind('$')
+                .and_then(|start| NmeaSentence::parse(&text[start..]));
+            line.clear();
+            let Some(sentence) = sentence else {
+                continue;
+            };
+
+            let time = sentence.field(0).map(str::to_string);
+            match sentence.kind.as_str() {
+                "GGA" => last_gga_time = time,
+                "RMC" if time.is_some() && time == last_gga_time => continue,
+                _ => {}
+            }
+            if let Some(position) = sentence.position() {
+                return Some(position);
+            }
+        }
+    })
+}
+
+// "$" + body + "*hh\r\n"
+pub fn nmea_sentence(body: &str) -> String {
+    format!("${}*{:02X}\r\n", body, nmea_checksum(body))
+}
+
+// Signed degrees to the ddmm.mmmmm (dddmm.mmmmm) field and its hemisphere
+fn nmea_coordinate_fields(value: f64, positive: char, negative: char, deg_width: usize) -> String {
+    let hemisphere = if value < 0.0 { negative } else { positive };
+    let value = value.abs();
+    let mut degrees = value.trunc();
+    let mut minutes = ((value - degrees) * 60.0 * 100_000.0).round() / 100_000.0;
+    if minutes >= 60.0 {
+        degrees += 1.0;
+        minutes -= 60.0;
+    }
+    format!(
+        "{:0width$}{:08.5},{}",
+        degrees as u32,
+        minutes,
+        hemisphere,
+        width = deg_width
+    )
+}
+
+fn nmea_time(utc: SystemTime) -> String {
+    let (_, _, _, hour, min, sec, ms) = utc_fields(utc);
+    format!("{:02}{:02}{:02}.{:02}", hour, min, sec, ms / 10)
+}
+
+/*
+    GPGGA sentence for a position. quality: 0 no fix, 1 GPS fix, 2 differential.
+
+    Altitude is the MSL height, the geoid separation comes from the built-in EGM96 grid.
+    Accuracy fields of Position have no place in GGA.
+*/
+pub fn gga_sentence(pos: &Position, utc: SystemTime, quality: u8, num_sv: u8, hdop: f64) -> String {
+    let body = format!(
+        "GPGGA,{},{},{},{},{:02},{:.1},{:.1},M,{:.1},M,,",
+        nmea_time(utc),
+        nmea_coordinate_fields(pos.lat, 'N', 'S', 2),
+        nmea_coordinate_fields(pos.lon, 'E', 'W', 3),
+        quality,
+        num_sv,
+        hdop,
+        pos.height_msl,
+        geoid_separation(pos.lat, pos.lon)
+    );
+    nmea_sentence(&body)
+}
+
+// GPRMC sentence (NMEA 2.3, with mode indicator). valid false marks the data void (V).
+// ground_speed in m/s, sent in knots.
+pub fn rmc_sentence(
+    pos: &Position,
+    utc: SystemTime,
+    valid: bool,
+    ground_speed: f64,
+    course: Bearing,
+) -> String {
+    let (year, month, day, ..) = utc_fields(utc);
+    let body = format!(
+        "GPRMC,{},{},{},{},{:.1},{:.1},{:02}{:02}{:02},,,{}",
+        nmea_time(utc),
+        if valid { 'A' } else { 'V' },
+        nmea_coordinate_fields(pos.lat, 'N', 'S', 2),
+        nmea_coordinate_fields(pos.lon, 'E', 'W', 3),
+        ground_speed * 3600.0 / 1852.0,
+        course.degrees(),
+        day,
+        month,
+        year.rem_euclid(100),
+        if valid { 'A' } else { 'N' }
+    );
+    nmea_sentence(&body)
+}
@@ -0,0 +1,66 @@
+use crate::Position;
+
+// Open Location Code digits, base 20
+const OLC_ALPHABET: &[u8; 20] = b"23456789CFGHJMPQRVWX";
+const OLC_SEPARATOR_POSITION: usize = 8;
+// pair section (5 lat / lon digit pairs), then up to 5 grid digits
+const OLC_PAIR_CODE_LENGTH: usize = 10;
+const OLC_GRID_CODE_LENGTH: usize = 5;
+pub const OLC_MAX_CODE_LENGTH: usize = OLC_PAIR_CODE_LENGTH + OLC_GRID_CODE_LENGTH;
+const OLC_GRID_COLUMNS: i64 = 4;
+const OLC_GRID_ROWS: i64 = 5;
+// units per degree at full length: 20^3 for the pairs, times the grid refinement
+const OLC_FINAL_LAT_PRECISION: i64 = 8000 * 3125;
+const OLC_FINAL_LNG_PRECISION: i64 = 8000 * 1024;
+
+impl Position {
+    /*
+        Open Location Code ("plus code"), e.g. 8FVC9G8F+6X, of length significant digits.
+
+        10 digits is a ~14 x 14 m cell, 11 about 3 x 3 m, which is as fine as a GNSS fix
+        deserves. Lengths are valid from 2 to 15, and below 10 only even, others are
+        clamped / rounded up to the next valid one. Short codes are padded with 0 up to the
+        separator (7FG40000+). Latitude is clipped to +/-90, longitude normalized.
+    */
+    pub fn to_plus_code(&self, length: usize) -> String {
+        let mut length = length.clamp(2, OLC_MAX_CODE_LENGTH);
+        if length < OLC_PAIR_CODE_LENGTH && length % 2 == 1 {
+            length += 1;
+        }
+
+        // integer units from the south west corner, rounded against float noise first
+        let to_units = |value: f64, precision: i64| {
+            ((value * precision as f64 * 1e6).round() / 1e6).floor() as i64
+        };
+        let mut lat = to_units(self.lat.clamp(-90.0, 90.0) + 90.0, OLC_FINAL_LAT_PRECISION);
+        let mut lng = to_units(self.lon + 180.0, OLC_FINAL_LNG_PRECISION)
+            .rem_euclid(360 * OLC_FINAL_LNG_PRECISION);
+        // the north pole belongs to the cell below it
+        lat = lat.min(180 * OLC_FINAL_LAT_PRECISION - 1);
+
+        let mut digits = Vec::with_capacity(OLC_MAX_CODE_LENGTH);
+        if length > OLC_PAIR_CODE_LENGTH {
+            for _ in 0..OLC_GRID_CODE_LENGTH {
+                let index = (lat % OLC_GRID_ROWS) * OLC_GRID_COLUMNS + lng % OLC_GRID_COLUMNS;
+                digits.push(OLC_ALPHABET[index as usize]);
+                lat /= OLC_GRID_ROWS;
+                lng /= OLC_GRID_COLUMNS;
+            }
+        } else {
+            lat /= OLC_GRID_ROWS.pow(OLC_GRID_CODE_LENGTH as u32);
+            lng /= OLC_GRID_COLUMNS.pow(OLC_GRID_CODE_LENGTH as u32);
+        }
+        for _ in 0..OLC_PAIR_CODE_LENGTH / 2 {
+            digits.push(OLC_ALPHABET[(lng % 20) as usize]);
+            digits.push(OLC_ALPHABET[(lat % 20) as usize]);
+            lat /= 20;
+            lng /= 20;
+        }
+        digits.reverse();
+        digits.truncate(length);
+        digits.resize(digits.len().max(OLC_SEPARATOR_POSITION), b'0');
+        digits.insert(OLC_SEPARATOR_POSITION, b'+');
+
+        String::from_utf8(digits).unwrap_or_default()
+    }
+}
@@ -0,0 +1,728 @@
+use crate::{
+    DecodedMessage, MAX_SENTENCE_LEN, Position, UBX_SYNC_1, UBX_SYNC_2, UbxHeader, UbxMessage,
+    decode_message, parse_nav_posllh, parse_nav_pvt, ubx_checksum,
+};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// How long a partial frame may sit in the buffer before it is considered stale.
+pub const DEFAULT_FRAME_TIMEOUT: Duration = Duration::from_secs(1);
+
+// NMEA sentences kept for next_nmea() before the oldest are dropped
+const NMEA_QUEUE_LEN: usize = 64;
+
+/*
+    Streaming UBX parser.
+
+    Serial reads do not line up with frame boundaries, a single read may hold half a
+    frame or several frames at once. The parser keeps whatever bytes it was given and
+    hands out complete, checksum-valid frames as they become available.
+
+    A sync word followed by a bogus length (cable pulled mid-message, line noise) would
+    otherwise make the parser wait forever for a payload that never arrives. If a
+    partial frame does not complete within the frame timeout it is discarded and the
+    parser resyncs on the bytes that followed it.
+
+    Every push is stamped with the host time it arrived, next_timestamped() returns a
+    frame together with the arrival time of the push holding its first sync byte, so the
+    stamp does not depend on how long the frame took to come in.
+
+    Receivers fresh from the factory interleave NMEA with UBX. A '$' between frames
+    starts an NMEA sentence which is skipped whole up to its line feed, instead of being
+    scanned byte by byte for a sync word. With set_capture_nmea(true) the skipped
+    sentences are kept for next_nmea().
+*/
+pub struct UbxParser {
+    buf: Vec<u8>,
+    frame_timeout: Option<Duration>,
+    frame_started: Option<Instant>,
+    emit_checksum_failures: bool,
+    // (total bytes pushed at the end of a push, arrival time), oldest first
+    arrivals: VecDeque<(usize, Instant)>,
+    pushed: usize,
+    consumed: usize,
+    stats: ParserStats,
+    nmea: Option<VecDeque<String>>,
+    // host clock, replaceable by tests
+    clock: fn() -> Instant,
+}
+
+/*
+    Link quality counters, since the parser was created.
+
+    frames_ok: checksum-valid frames returned
+    checksum_errors: frames dropped (or reported) for a checksum mismatch
+    bytes_discarded: bytes thrown away, noise between frames and the sync words of
+    abandoned frames
+    resyncs: frames abandoned and rescanned for the next sync word, after a checksum
+    mismatch or a partial frame going stale
+    nmea_sentences: NMEA sentences skipped between frames
+*/
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParserStats {
+    pub frames_ok: u64,
+    pub checksum_errors: u64,
+    pub bytes_discarded: u64,
+    pub resyncs: u64,
+    pub nmea_sentences: u64,
+}
+
+// What follows a '$' at the start of the buffer
+enum NmeaScan {
+    // a complete sentence of this many bytes, line feed included
+    Line(usize),
+    // no line feed yet
+    Partial,
+    // binary data or runaway line, the '$' was noise
+    NotNmea,
+}
+
+// A frame with the host time its first sync byte was handed to the parser
+#[derive(Debug)]
+pub struct TimestampedMessage {
+    pub message: UbxMessage,
+    pub received_at: Instant,
+}
+
+/*
+    A position with the host time its frame was received, from UbxParser::next_fix().
+
+    age() is host relative, so it stays meaningful for buffered or queued data where the
+    fix's own GPS time says nothing about how long it sat in a buffer. Replayed logs need
+    push_at() with the original receive times for it to mean anything.
+*/
+#[derive(Debug, Clone)]
+pub struct TimestampedFix {
+    pub pos: Position,
+    pub received: Instant,
+}
+
+impl TimestampedFix {
+    // From NAV-POSLLH, or NAV-PVT with gnssFixOK set. None for any other message and for
+    // a NAV-PVT without a valid fix (NAV-POSLLH carries no fix flag).
+    pub fn from_message(msg: &TimestampedMessage) -> Option<TimestampedFix> {
+        let payload = &msg.message.payload;
+        let pos = match (msg.message.class, msg.message.id) {
+            (0x01, 0x02) => parse_nav_posllh(payload)?,
+            (0x01, 0x07) => {
+                let pvt = parse_nav_pvt(payload)?;
+                if !pvt.gnss_fix_ok() {
+                    return None;
+                }
+                Position::from(&pvt)
+            }
+            _ => return None,
+        };
+        Some(TimestampedFix {
+            pos,
+            received: msg.received_at,
+        })
+    }
+
+    pub fn age(&self) -> Duration {
+        self.received.elapsed()
+    }
+
+    pub fn is_older_than(&self, max_age: Duration) -> bool {
+        self.age() > max_age
+    }
+}
+
+// A frame whose checksum did not match, kept for link quality debugging
+#[derive(Debug, Clone)]
+pub struct CorruptFrame {
+    pub class: u8,
+    pub id: u8,
+    pub payload: Vec<u8>,
+    // checksum computed over the received bytes
+    pub expected_ck: (u8, u8),
+    // checksum carried by the frame
+    pub actual_ck: (u8, u8),
+}
+
+#[derive(Debug)]
+pub enum ParserEvent {
+    Message(UbxMessage),
+    ChecksumFailure(CorruptFrame),
+}
+
+impl Default for UbxParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UbxParser {
+    pub fn new() -> Self {
+        UbxParser {
+            buf: Vec::new(),
+            frame_timeout: Some(DEFAULT_FRAME_TIMEOUT),
+            frame_started: None,
+            emit_checksum_failures: false,
+            arrivals: VecDeque::new(),
+            pushed: 0,
+            consumed: 0,
+            stats: ParserStats::default(),
+            nmea: None,
+            clock: Instant::now,
+        }
+    }
+
+    // When set, frames failing the checksum are reported by next_event() instead of
+    // being silently dropped. next_message() never returns them.
+    pub fn set_emit_checksum_failures(&mut self, emit: bool) {
+        self.emit_checksum_failures = emit;
+    }
+
+    pub fn emit_checksum_failures(&self) -> bool {
+        self.emit_checksum_failures
+    }
+
+    // None disables the staleness check, partial frames are then kept until completed.
+    pub fn set_frame_timeout(&mut self, timeout: Option<Duration>) {
+        self.frame_timeout = timeout;
+    }
+
+    pub fn frame_timeout(&self) -> Option<Duration> {
+        self.frame_timeout
+    }
+
+    pub fn push(&mut self, data: &[u8]) {
+        self.push_at(data, (self.clock)());
+    }
+
+    // Push bytes stamped with the time they were read, for callers timestamping at the
+    // transport for better precision
+    pub fn push_at(&mut self, data: &[u8], received_at: Instant) {
+        if data.is_empty() {
+            return;
+        }
+        self.buf.extend_from_slice(data);
+        self.pushed += data.len();
+        self.arrivals.push_back((self.pushed, received_at));
+    }
+
+    // When set, NMEA sentences skipped between frames are kept (the latest 64) for
+    // next_nmea()
+    pub fn set_capture_nmea(&mut self, capture: bool) {
+        self.nmea = capture.then(VecDeque::new);
+    }
+
+    // Next skipped NMEA sentence, without line terminator. Call after next_message() etc.
+    // has consumed the buffered frames, sentences are only seen while scanning for them.
+    pub fn next_nmea(&mut self) -> Option<String> {
+        self.nmea.as_mut()?.pop_front()
+    }
+
+    pub fn stats(&self) -> ParserStats {
+        self.stats
+    }
+
+    // Number of bytes buffered but not yet returned as a frame
+    pub fn buffered(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn next_message(&mut self) -> Option<UbxMessage> {
+        self.next_timestamped().map(|t| t.message)
+    }
+
+    pub fn next_timestamped(&mut self) -> Option<TimestampedMessage> {
+        loop {
+            match self.next_frame()? {
+                (ParserEvent::Message(message), received_at) => {
+                    return Some(TimestampedMessage {
+                        message,
+                        received_at,
+                    });
+                }
+                (ParserEvent::ChecksumFailure(_), _) => continue,
+            }
+        }
+    }
+
+    // Next NAV-POSLLH / valid NAV-PVT position with its receive time, frames of every
+    // other type and NAV-PVT without a fix are consumed and dropped
+    pub fn next_fix(&mut self) -> Option<TimestampedFix> {
+        loop {
+            let msg = self.next_timestamped()?;
+            if let Some(fix) = TimestampedFix::from_message(&msg) {
+                return Some(fix);
+            }
+        }
+    }
+
+    // Next frame decoded via decode_message, e.g. ACK / NAK frames as DecodedMessage::Ack
+    pub fn next_decoded(&mut self) -> Option<DecodedMessage> {
+        self.next_message().map(decode_message)
+    }
+
+    pub fn next_event(&mut self) -> Option<ParserEvent> {
+        self.next_frame().map(|(event, _)| event)
+    }
+
+    fn next_frame(&mut self) -> Option<(ParserEvent, Instant)> {
+        loop {
+            if !self.sync() {
+                return None;
+            }
+
+            let Some(header) = UbxHeader::parse(&self.buf) else {
+                if self.is_stale() {
+                    self.discard_frame();
+                    continue;
+                }
+                return None;
+            };
+
+            let len = header.length as usize;
+            if self.buf.len() < 8 + len {
+                if self.is_stale() {
+                    self.discard_frame();
+                    continue;
+                }
+                return None;
+            }
+
+            let received_at = self.arrival_of(1);
+            let expected_ck = ubx_checksum(&self.buf[2..6 + len]);
+            let actual_ck = (self.buf[6 + len], self.buf[7 + len]);
+            if expected_ck != actual_ck {
+                // The length field may be the corrupted part, resync inside the frame
+                self.stats.checksum_errors += 1;
+                let corrupt = self.emit_checksum_failures.then(|| CorruptFrame {
+                    class: header.class,
+                    id: header.id,
+                    payload: self.buf[6..6 + len].to_vec(),
+                    expected_ck,
+                    actual_ck,
+                });
+                self.discard_frame();
+                match corrupt {
+                    Some(frame) => {
+                        return Some((ParserEvent::ChecksumFailure(frame), received_at));
+                    }
+                    None => continue,
+                }
+            }
+
+            let payload = self.buf[6..6 + len].to_vec();
+            self.consume(8 + len);
+            self.frame_started = None;
+            self.stats.frames_ok += 1;
+
+            let msg = UbxMessage {
+                class: header.class,
+                id: header.id,
+                payload,
+            };
+            return Some((ParserEvent::Message(msg), received_at));
+        }
+    }
+
+    // Drops everything before the next sync word, skipping NMEA sentences on the way.
+    // Returns true if a sync word is at the start of the buffer.
+    fn sync(&mut self) -> bool {
+        loop {
+            if self.buf.first() == Some(&b'$') {
+                match self.scan_nmea() {
+                    NmeaScan::Line(len) => self.take_nmea(len),
+                    NmeaScan::Partial => return false,
+                    NmeaScan::NotNmea => self.discard(1),
+                }
+                continue;
+            }
+
+            let next = (0..self.buf.len()).find(|&i| {
+                self.buf[i] == b'$' || self.buf[i..].starts_with(&[UBX_SYNC_1, UBX_SYNC_2])
+            });
+            match next {
+                Some(0) => {
+                    if self.frame_started.is_none() {
+                        self.frame_started = Some((self.clock)());
+                    }
+                    return true;
+                }
+                Some(pos) => self.discard(pos),
+                None => {
+                    // Keep a trailing first sync byte, the second one may be in the next read
+                    let keep = usize::from(self.buf.last() == Some(&UBX_SYNC_1));
+                    self.discard(self.buf.len() - keep);
+                    return false;
+                }
+            }
+        }
+    }
+
+    fn scan_nmea(&self) -> NmeaScan {
+        for (i, &b) in self.buf.iter().enumerate().skip(1).take(MAX_SENTENCE_LEN) {
+            match b {
+                b'\n' => return NmeaScan::Line(i + 1),
+                b'\r' | 0x20..=0x7E => {}
+                _ => return NmeaScan::NotNmea,
+            }
+        }
+        if self.buf.len() > MAX_SENTENCE_LEN {
+            NmeaScan::NotNmea
+        } else {
+            NmeaScan::Partial
+        }
+    }
+
+    fn take_nmea(&mut self, len: usize) {
+        self.stats.nmea_sentences += 1;
+        if let Some(queue) = self.nmea.as_mut() {
+            let line = String::from_utf8_lossy(&self.buf[..len]);
+            if queue.len() == NMEA_QUEUE_LEN {
+                queue.pop_front();
+            }
+            queue.push_back(line.trim_end_matches(['\r', '\n']).to_string());
+        }
+        self.consume(len);
+    }
+
+    // Drops n bytes of noise
+    fn discard(&mut self, n: usize) {
+        self.stats.bytes_discarded += n as u64;
+        self.consume(n);
+        self.frame_started = None;
+    }
+
+    fn is_stale(&self) -> bool {
+        match (self.frame_timeout, self.frame_started) {
+            (Some(timeout), Some(started)) => {
+                (self.clock)().saturating_duration_since(started) >= timeout
+            }
+            _ => false,
+        }
+    }
+
+    // Skip the sync word of the current frame so the scan resumes right after it
+    fn discard_frame(&mut self) {
+        self.stats.resyncs += 1;
+        self.stats.bytes_discarded += 2;
+        self.consume(2);
+        self.frame_started = None;
+    }
+
+    fn consume(&mut self, n: usize) {
+        self.buf.drain(..n);
+        self.consumed += n;
+        while matches!(self.arrivals.front(), Some(&(end, _)) if end <= self.consumed) {
+            self.arrivals.pop_front();
+        }
+    }
+
+    // Arrival time of the push holding the byte at buffer index len - 1
+    fn arrival_of(&self, len: usize) -> Instant {
+        let end = self.consumed + len;
+        self.arrivals
+            .iter()
+            .find(|&&(pushed, _)| pushed >= end)
+            .map(|&(_, at)| at)
+            .unwrap_or_else(self.clock)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build_ubx_frame;
+    use std::cell::Cell;
+
+    thread_local! {
+        static NOW: Cell<Option<Instant>> = const { Cell::new(None) };
+    }
+
+    // Test clock, starts at the first call and only moves with advance()
+    fn test_clock() -> Instant {
+        NOW.with(|now| *now.get().get_or_insert_with(Instant::now))
+    }
+
+    fn advance(by: Duration) {
+        let at = test_clock() + by;
+        NOW.with(|now| now.set(Some(at)));
+    }
+
+    fn test_parser() -> UbxParser {
+        let mut parser = UbxParser::new();
+        parser.clock = test_clock;
+        parser
+    }
+
+    const POSLLH_POLL: [u8; 8] = [0xB5, 0x62, 0x01, 0x02, 0x00, 0x00, 0x03, 0x0A];
+
+    #[test]
+    fn stale_partial_frame_is_dropped_after_gap() {
+        let mut parser = test_parser();
+        // sync word and a length of 1000, the payload never comes
+        parser.push(&[0xB5, 0x62, 0x01, 0x07, 0xE8, 0x03, 0x01, 0x02]);
+        assert!(parser.next_message().is_none());
+
+        advance(DEFAULT_FRAME_TIMEOUT / 2);
+        assert!(parser.next_message().is_none());
+        assert_eq!(parser.stats().resyncs, 0);
+        assert_eq!(parser.buffered(), 8);
+
+        advance(DEFAULT_FRAME_TIMEOUT);
+        assert!(parser.next_message().is_none());
+        assert_eq!(parser.stats().resyncs, 1);
+        assert_eq!(parser.buffered(), 0);
+    }
+
+    #[test]
+    fn resyncs_on_next_sync_word_after_stale_frame() {
+        let mut parser = test_parser();
+        parser.push(&[0xB5, 0x62, 0x01, 0x07, 0xE8, 0x03, 0x55, 0xAA]);
+        assert!(parser.next_message().is_none());
+
+        advance(DEFAULT_FRAME_TIMEOUT * 2);
+        parser.push(&POSLLH_POLL);
+        let msg = parser.next_message().unwrap();
+        assert_eq!((msg.class, msg.id), (0x01, 0x02));
+        assert_eq!(parser.stats().resyncs, 1);
+        assert_eq!(parser.buffered(), 0);
+    }
+
+    #[test]
+    fn partial_frame_is_kept_without_timeout() {
+        let mut parser = test_parser();
+        parser.set_frame_timeout(None);
+        let frame = build_ubx_frame(0x01, 0x03, &[0; 16]);
+        parser.push(&frame[..10]);
+        assert!(parser.next_message().is_none());
+
+        advance(DEFAULT_FRAME_TIMEOUT * 10);
+        parser.push(&frame[10..]);
+        assert_eq!(parser.next_message().unwrap().id, 0x03);
+        assert_eq!(parser.stats().resyncs, 0);
+    }
+
+    // NAV-STATUS frame with its checksum broken
+    fn corrupt_frame() -> Vec<u8> {
+        let mut frame = build_ubx_frame(0x01, 0x03, &[7; 16]);
+        let last = frame.len() - 1;
+        frame[last] ^= 0xFF;
+        frame
+    }
+
+    #[test]
+    fn checksum_failure_is_dropped_by_default() {
+        let mut parser = test_parser();
+        parser.push(&corrupt_frame());
+        parser.push(&POSLLH_POLL);
+
+        match parser.next_event() {
+            Some(ParserEvent::Message(msg)) => assert_eq!(msg.id, 0x02),
+            other => panic!("expected the good frame, got {:?}", other),
+        }
+        assert!(parser.next_event().is_none());
+        assert_eq!(parser.stats().checksum_errors, 1);
+        assert_eq!(parser.stats().frames_ok, 1);
+    }
+
+    #[test]
+    fn checksum_failure_is_emitted_when_enabled() {
+        let mut parser = test_parser();
+        parser.set_emit_checksum_failures(true);
+        let frame = corrupt_frame();
+        parser.push(&frame);
+        parser.push(&POSLLH_POLL);
+
+        match parser.next_event() {
+            Some(ParserEvent::ChecksumFailure(corrupt)) => {
+                assert_eq!((corrupt.class, corrupt.id), (0x01, 0x03));
+                assert_eq!(corrupt.payload, [7; 16]);
+                let n = frame.len();
+                assert_eq!(corrupt.actual_ck, (frame[n - 2], frame[n - 1]));
+                assert_eq!(corrupt.expected_ck, (frame[n - 2], frame[n - 1] ^ 0xFF));
+            }
+            other => panic!("expected a checksum failure, got {:?}", other),
+        }
+        assert!(matches!(parser.next_event(), Some(ParserEvent::Message(_))));
+        assert_eq!(parser.stats().checksum_errors, 1);
+        assert_eq!(parser.stats().resyncs, 1);
+    }
+
+    #[test]
+    fn next_message_never_returns_checksum_failures() {
+        let mut parser = test_parser();
+        parser.set_emit_checksum_failures(true);
+        parser.push(&corrupt_frame());
+        parser.push(&POSLLH_POLL);
+
+        assert_eq!(parser.next_message().unwrap().id, 0x02);
+        assert_eq!(parser.stats().checksum_errors, 1);
+    }
+
+    #[test]
+    fn timestamp_is_arrival_of_first_sync_byte() {
+        let mut parser = test_parser();
+        let frame = build_ubx_frame(0x01, 0x07, &[0; 92]);
+        let start = test_clock();
+
+        // noise, then the frame split over three reads 10 ms apart
+        parser.push_at(&[0x00, 0x11], start);
+        parser.push_at(&frame[..1], start + Duration::from_millis(10));
+        parser.push_at(&frame[1..50], start + Duration::from_millis(20));
+        assert!(parser.next_timestamped().is_none());
+        parser.push_at(&frame[50..], start + Duration::from_millis(30));
+
+        let msg = parser.next_timestamped().unwrap();
+        assert_eq!(msg.message.id, 0x07);
+        assert_eq!(msg.received_at, start + Duration::from_millis(10));
+    }
+
+    #[test]
+    fn frames_of_one_read_share_its_timestamp() {
+        let mut parser = test_parser();
+        let at = test_clock() + Duration::from_millis(5);
+        let mut data = POSLLH_POLL.to_vec();
+        data.extend(build_ubx_frame(0x01, 0x03, &[0; 16]));
+        parser.push_at(&data, at);
+
+        assert_eq!(parser.next_timestamped().unwrap().received_at, at);
+        assert_eq!(parser.next_timestamped().unwrap().received_at, at);
+    }
+
+    #[test]
+    fn stats_count_mixed_stream() {
+        let mut parser = test_parser();
+        let good = build_ubx_frame(0x01, 0x02, &[1; 28]);
+        let corrupt = corrupt_frame();
+        let mut data = vec![0x00, 0x11, 0x22];
+        data.extend(b"$GPGGA,,,,,,0,00,99.99,,,,,,*48\r\n");
+        data.extend(&good);
+        data.extend(&corrupt);
+        data.extend([0x33, 0x44]);
+        data.extend(&POSLLH_POLL);
+        data.extend(b"$GPTXT,01,01,02,ANTSTATUS=OK*3B\r\n");
+        // first sync byte of a frame yet to come
+        data.push(0xB5);
+        parser.push(&data);
+
+        while parser.next_message().is_some() {}
+        let stats = parser.stats();
+        assert_eq!(stats.frames_ok, 2);
+        assert_eq!(stats.checksum_errors, 1);
+        assert_eq!(stats.resyncs, 1);
+        assert_eq!(stats.nmea_sentences, 2);
+        // leading noise, the whole corrupt frame (sync word, then the rest scanned as
+        // noise), the noise between frames
+        assert_eq!(stats.bytes_discarded, 3 + corrupt.len() as u64 + 2);
+        assert_eq!(parser.buffered(), 1);
+    }
+
+    #[test]
+    fn stats_start_at_zero() {
+        assert_eq!(test_parser().stats(), ParserStats::default());
+    }
+
+    #[test]
+    fn nmea_interleaved_with_ubx_is_captured() {
+        let mut parser = test_parser();
+        parser.set_capture_nmea(true);
+        parser.push(b"$GPRMC,,V,,,,,,,,,,N*53\r\n");
+        parser.push(&POSLLH_POLL);
+        // a sentence split over two reads
+        parser.push(b"$GPGSA,A,1,,,,,,,,,");
+        parser.push(b",,,,99.99,99.99,99.99*30\r\n");
+        parser.push(&build_ubx_frame(0x01, 0x03, &[0; 16]));
+
+        assert_eq!(parser.next_message().unwrap().id, 0x02);
+        assert_eq!(parser.next_message().unwrap().id, 0x03);
+        assert!(parser.next_message().is_none());
+
+        assert_eq!(parser.next_nmea().unwrap(), "$GPRMC,,V,,,,,,,,,,N*53");
+        assert_eq!(
+            parser.next_nmea().unwrap(),
+            "$GPGSA,A,1,,,,,,,,,,,,,99.99,99.99,99.99*30"
+        );
+        assert!(parser.next_nmea().is_none());
+        assert_eq!(parser.stats().bytes_discarded, 0);
+    }
+
+    #[test]
+    fn nmea_queue_drops_oldest_on_overflow() {
+        let mut parser = test_parser();
+        parser.set_capture_nmea(true);
+        for i in 0..NMEA_QUEUE_LEN + 6 {
+            parser.push(format!("$GPTXT,{:03}\r\n", i).as_bytes());
+            parser.push(&POSLLH_POLL);
+        }
+        while parser.next_message().is_some() {}
+
+        let captured: Vec<String> = std::iter::from_fn(|| parser.next_nmea()).collect();
+        assert_eq!(captured.len(), NMEA_QUEUE_LEN);
+        assert_eq!(captured[0], "$GPTXT,006");
+        assert_eq!(
+            captured[NMEA_QUEUE_LEN - 1],
+            format!("$GPTXT,{:03}", NMEA_QUEUE_LEN + 5)
+        );
+        assert_eq!(parser.stats().nmea_sentences, NMEA_QUEUE_LEN as u64 + 6);
+    }
+
+    #[test]
+    fn nmea_is_skipped_without_capture() {
+        let mut parser = test_parser();
+        parser.push(b"$GPRMC,,V,,,,,,,,,,N*53\r\n");
+        parser.push(&POSLLH_POLL);
+
+        assert_eq!(parser.next_message().unwrap().id, 0x02);
+        assert!(parser.next_nmea().is_none());
+        assert_eq!(parser.stats().nmea_sentences, 1);
+    }
+
+    // NAV-PVT at lat / lon (degrees), 3D fix with gnssFixOK if fix_ok, else no fix
+    fn nav_pvt_frame(lat: f64, lon: f64, fix_ok: bool) -> Vec<u8> {
+        let mut payload = [0u8; 92];
+        payload[20] = if fix_ok { 3 } else { 0 };
+        payload[21] = u8::from(fix_ok);
+        payload[24..28].copy_from_slice(&((lon * 1e7).round() as i32).to_le_bytes());
+        payload[28..32].copy_from_slice(&((lat * 1e7).round() as i32).to_le_bytes());
+        build_ubx_frame(0x01, 0x07, &payload)
+    }
+
+    #[test]
+    fn next_fix_returns_only_valid_fixes() {
+        let mut parser = test_parser();
+        let start = test_clock();
+        parser.push_at(&nav_pvt_frame(1.0, 2.0, false), start);
+        parser.push_at(&build_ubx_frame(0x01, 0x03, &[0; 16]), start);
+        parser.push_at(
+            &nav_pvt_frame(47.5, 8.5, true),
+            start + Duration::from_millis(100),
+        );
+        parser.push_at(
+            &nav_pvt_frame(3.0, 4.0, false),
+            start + Duration::from_millis(200),
+        );
+        parser.push_at(
+            &nav_pvt_frame(47.6, 8.6, true),
+            start + Duration::from_millis(300),
+        );
+
+        let first = parser.next_fix().unwrap();
+        assert!((first.pos.lat - 47.5).abs() < 1e-9 && (first.pos.lon - 8.5).abs() < 1e-9);
+        assert_eq!(first.received, start + Duration::from_millis(100));
+
+        let second = parser.next_fix().unwrap();
+        assert!((second.pos.lat - 47.6).abs() < 1e-9);
+        assert_eq!(second.received, start + Duration::from_millis(300));
+
+        assert!(parser.next_fix().is_none());
+        assert_eq!(parser.buffered(), 0);
+    }
+
+    #[test]
+    fn fix_age_is_host_relative() {
+        let Some(received) = Instant::now().checked_sub(Duration::from_secs(5)) else {
+            return;
+        };
+        let pos = parse_nav_posllh(&[0; 28]).unwrap();
+        let fix = TimestampedFix { pos, received };
+        assert!(fix.age() >= Duration::from_secs(5));
+        assert!(fix.is_older_than(Duration::from_secs(2)));
+        assert!(!fix.is_older_than(Duration::from_secs(60)));
+    }
+}
@@ -0,0 +1,127 @@
+use crate::{NavDop, NavSat, Position};
+use std::fmt;
+
+/*
+    Horizontal accuracy estimate as a category, for labelling fixes in logs and UIs.
+
+    SubMeter: below 1 m (SBAS / DGPS corrected, open sky)
+    FewMeters: 1 m to below 5 m (typical standalone fix)
+    TensOfMeters: 5 m to below 50 m (urban canyon, few satellites)
+    Poor: 50 m and above, or no estimate (NaN)
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AccuracyBand {
+    SubMeter,
+    FewMeters,
+    TensOfMeters,
+    Poor,
+}
+
+impl AccuracyBand {
+    pub fn from_meters(accuracy: f64) -> AccuracyBand {
+        match accuracy {
+            a if a < 1.0 => AccuracyBand::SubMeter,
+            a if a < 5.0 => AccuracyBand::FewMeters,
+            a if a < 50.0 => AccuracyBand::TensOfMeters,
+            _ => AccuracyBand::Poor,
+        }
+    }
+}
+
+impl fmt::Display for AccuracyBand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccuracyBand::SubMeter => write!(f, "sub-meter"),
+            AccuracyBand::FewMeters => write!(f, "few meters"),
+            AccuracyBand::TensOfMeters => write!(f, "tens of meters"),
+            AccuracyBand::Poor => write!(f, "poor"),
+        }
+    }
+}
+
+impl Position {
+    pub fn accuracy_band(&self) -> AccuracyBand {
+        AccuracyBand::from_meters(self.horizontal_accuracy)
+    }
+
+    // e.g. "±2m (few meters)"
+    pub fn accuracy_label(&self) -> String {
+        if self.horizontal_accuracy.is_nan() {
+            return format!("±?m ({})", self.accuracy_band());
+        }
+        format!(
+            "±{:.0}m ({})",
+            self.horizontal_accuracy,
+            self.accuracy_band()
+        )
+    }
+
+    /*
+        Horizontal position covariance in m², [[north, north-east], [east-north, east]],
+        for Kalman / EKF fusion.
+
+        The receiver only reports a single hAcc, so the error is assumed isotropic and
+        uncorrelated: horizontal_accuracy is taken as the 1 sigma of each axis and the
+        matrix is diagonal. Real errors are usually elongated (along the worst satellite
+        geometry), treat this as a hint rather than the true shape. An unknown accuracy
+        (NaN, e.g. from NMEA) gives infinite variances, i.e. no information.
+    */
+    pub fn horizontal_covariance(&self) -> [[f64; 2]; 2] {
+        let variance = if self.horizontal_accuracy.is_finite() {
+            self.horizontal_accuracy.powi(2)
+        } else {
+            f64::INFINITY
+        };
+        [[variance, 0.0], [0.0, variance]]
+    }
+}
+
+/*
+    Overall fix quality from the geometry (HDOP) and the satellites used, for dashboards.
+
+    HDOP bands: Excellent below 1, Good 1 to 2, Moderate 2 to 5, Fair 5 to 10, Poor above.
+    Fewer than 4 satellites used is Poor (no 3D solution), fewer than 6 is at best
+    Moderate, as a single lost satellite then degrades the solution sharply.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FixQuality {
+    Excellent,
+    Good,
+    Moderate,
+    Fair,
+    Poor,
+}
+
+impl FixQuality {
+    pub fn from_hdop(hdop: f64) -> FixQuality {
+        match hdop {
+            h if h < 1.0 => FixQuality::Excellent,
+            h if h <= 2.0 => FixQuality::Good,
+            h if h <= 5.0 => FixQuality::Moderate,
+            h if h <= 10.0 => FixQuality::Fair,
+            _ => FixQuality::Poor,
+        }
+    }
+}
+
+impl fmt::Display for FixQuality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FixQuality::Excellent => write!(f, "excellent"),
+            FixQuality::Good => write!(f, "good"),
+            FixQuality::Moderate => write!(f, "moderate"),
+            FixQuality::Fair => write!(f, "fair"),
+            FixQuality::Poor => write!(f, "poor"),
+        }
+    }
+}
+
+// dop and sat should be of the same epoch (same i_tow)
+pub fn classify_fix_quality(dop: &NavDop, sat: &NavSat) -> FixQuality {
+    let quality = FixQuality::from_hdop(dop.hdop);
+    match sat.used_count() {
+        0..4 => FixQuality::Poor,
+        4..6 => quality.max(FixQuality::Moderate),
+        _ => quality,
+    }
+}
@@ -0,0 +1,173 @@
+use crate::{
+    DEFAULT_POLL_TIMEOUT, MonVer, NavDop, NavSat, NavStatus, Position, ResetMode, UbxError,
+    flush_input, parse_mon_ver, parse_nav_dop, parse_nav_posllh, parse_nav_sat, parse_nav_status,
+    reset_receiver, send_ubx_command, wait_for_message,
+};
+use serialport::SerialPort;
+use std::fmt;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+// Snapshot of the standard health messages, a field is None if the poll went unanswered
+#[derive(Debug, Clone, Default)]
+pub struct DeviceReport {
+    pub version: Option<MonVer>,
+    pub status: Option<NavStatus>,
+    pub satellites: Option<NavSat>,
+    pub dop: Option<NavDop>,
+    pub position: Option<Position>,
+}
+
+fn poll_once(port: &mut dyn SerialPort, class: u8, id: u8, timeout: Duration) -> Option<Vec<u8>> {
+    send_ubx_command(port, class, id, &[]).ok()?;
+    wait_for_message(port, class, id, timeout).map(|msg| msg.payload)
+}
+
+// Polls MON-VER, NAV-STATUS, NAV-SAT, NAV-DOP and NAV-POSLLH, waiting up to timeout for each
+pub fn device_report(port: &mut dyn SerialPort, timeout: Duration) -> DeviceReport {
+    DeviceReport {
+        version: poll_once(port, 0x0A, 0x04, timeout).and_then(|p| parse_mon_ver(&p)),
+        status: poll_once(port, 0x01, 0x03, timeout).and_then(|p| parse_nav_status(&p)),
+        satellites: poll_once(port, 0x01, 0x35, timeout).and_then(|p| parse_nav_sat(&p)),
+        dop: poll_once(port, 0x01, 0x04, timeout).and_then(|p| parse_nav_dop(&p)),
+        position: poll_once(port, 0x01, 0x02, timeout).and_then(|p| parse_nav_posllh(&p)),
+    }
+}
+
+impl fmt::Display for DeviceReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const NO_RESPONSE: &str = "no response";
+
+        writeln!(f, "===== u-blox Device Report =====")?;
+
+        match &self.version {
+            Some(ver) => {
+                writeln!(f, "Software:   {}", ver.sw_version)?;
+                writeln!(f, "Hardware:   {}", ver.hw_version)?;
+                for ext in &ver.extensions {
+                    writeln!(f, "Extension:  {}", ext)?;
+                }
+            }
+            None => writeln!(f, "Version:    {}", NO_RESPONSE)?,
+        }
+
+        match &self.status {
+            Some(status) => writeln!(
+                f,
+                "Fix:        {} ({}) | TTFF: {} ms",
+                status.gps_fix,
+                if status.gps_fix_ok() {
+                    "valid"
+                } else {
+                    "invalid"
+                },
+                status.ttff
+            )?,
+            None => writeln!(f, "Fix:        {}", NO_RESPONSE)?,
+        }
+
+        match &self.satellites {
+            Some(sat) => writeln!(
+                f,
+                "Satellites: {} used / {} tracked / {} visible",
+                sat.used_count(),
+                sat.filtered(1).visible_count(),
+                sat.visible_count()
+            )?,
+            None => writeln!(f, "Satellites: {}", NO_RESPONSE)?,
+        }
+
+        match &self.dop {
+            Some(dop) => writeln!(
+                f,
+                "DOP:        G {:.2} | P {:.2} | H {:.2} | V {:.2} | T {:.2}",
+                dop.gdop, dop.pdop, dop.hdop, dop.vdop, dop.tdop
+            )?,
+            None => writeln!(f, "DOP:        {}", NO_RESPONSE)?,
+        }
+
+        match &self.position {
+            Some(pos) => writeln!(
+                f,
+                "Position:   {:.7}°, {:.7}° | {:.2} m (MSL) | H-Acc {:.2} m | V-Acc {:.2} m",
+                pos.lat, pos.lon, pos.height_msl, pos.horizontal_accuracy, pos.vertical_accuracy
+            )?,
+            None => writeln!(f, "Position:   {}", NO_RESPONSE)?,
+        }
+
+        write!(f, "================================")
+    }
+}
+
+// Pause between polls of measure_ttff / wait_for_satellites
+const STATUS_POLL_INTERVAL: Duration = Duration::from_millis(250);
+// Slack between the host clock and the receiver's msss when telling a NAV-STATUS from
+// after the reset apart from an older one
+const RESET_MSSS_SLACK_MS: u128 = 1_000;
+
+/*
+    Time to first fix from a cold start, measured on the host clock.
+
+    Cold starts the receiver, drops whatever was received before, then polls NAV-STATUS
+    until gpsFixOk is set. A fix only counts once the receiver has shown it restarted:
+    a NAV-STATUS without fix, or one whose msss (time since reset) fits the time since
+    the reset, so a status still in flight from before cannot end the wait at once.
+    Returns UbxError::Timeout if no valid fix is reported within timeout.
+*/
+pub fn measure_ttff(port: &mut dyn SerialPort, timeout: Duration) -> Result<Duration, UbxError> {
+    reset_receiver(port, ResetMode::Cold)?;
+    let start = Instant::now();
+    flush_input(port)?;
+    let mut restarted = false;
+
+    while start.elapsed() < timeout {
+        let remaining = timeout.saturating_sub(start.elapsed());
+        send_ubx_command(port, 0x01, 0x03, &[])?;
+
+        if let Some(status) =
+            wait_for_message(port, 0x01, 0x03, remaining.min(DEFAULT_POLL_TIMEOUT))
+                .and_then(|msg| parse_nav_status(&msg.payload))
+        {
+            restarted |= !status.gps_fix_ok()
+                || status.msss as u128 <= start.elapsed().as_millis() + RESET_MSSS_SLACK_MS;
+            if restarted && status.gps_fix_ok() {
+                return Ok(start.elapsed());
+            }
+        }
+
+        sleep(STATUS_POLL_INTERVAL.min(timeout.saturating_sub(start.elapsed())));
+    }
+
+    Err(UbxError::Timeout)
+}
+
+/*
+    Polls NAV-SAT until at least min_used satellites are used in the solution with a
+    C/N0 of min_cno dBHz or better, e.g. to hold off logging until the sky is good.
+
+    Returns the NAV-SAT that met the criteria, or UbxError::Timeout.
+*/
+pub fn wait_for_satellites(
+    port: &mut dyn SerialPort,
+    min_used: usize,
+    min_cno: u8,
+    timeout: Duration,
+) -> Result<NavSat, UbxError> {
+    let start = Instant::now();
+
+    while start.elapsed() < timeout {
+        let remaining = timeout.saturating_sub(start.elapsed());
+        send_ubx_command(port, 0x01, 0x35, &[])?;
+
+        if let Some(sat) = wait_for_message(port, 0x01, 0x35, remaining.min(DEFAULT_POLL_TIMEOUT))
+            .and_then(|msg| parse_nav_sat(&msg.payload))
+            && sat.filtered(min_cno).used_count() >= min_used
+        {
+            return Ok(sat);
+        }
+
+        sleep(STATUS_POLL_INTERVAL.min(timeout.saturating_sub(start.elapsed())));
+    }
+
+    Err(UbxError::Timeout)
+}
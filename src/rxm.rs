@@ -0,0 +1,26 @@
+// UBX-RXM-SFRB (0x02 0x11), one navigation message subframe as broadcast by the satellite
+#[derive(Debug, Clone)]
+pub struct RxmSfrb {
+    pub chn: u8,
+    pub svid: u8,
+    // the ten 30 bit words of the subframe, parity stripped for GPS, right aligned
+    pub dwrd: [u32; 10],
+}
+
+pub fn parse_rxm_sfrb(payload: &[u8]) -> Option<RxmSfrb> {
+    // Fixed 42 byte message, anything else is not a subframe
+    if payload.len() != 42 {
+        return None;
+    }
+
+    let mut dwrd = [0u32; 10];
+    for (word, bytes) in dwrd.iter_mut().zip(payload[2..].chunks_exact(4)) {
+        *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+
+    Some(RxmSfrb {
+        chn: payload[0],
+        svid: payload[1],
+        dwrd,
+    })
+}
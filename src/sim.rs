@@ -0,0 +1,306 @@
+use crate::{
+    Position, UBX_CLASS_ACK, UBX_ID_ACK_ACK, UbxParser, build_ubx_frame, meters_per_degree,
+    msl_to_ellipsoid_height,
+};
+#[cfg(feature = "serialport")]
+use serialport::{ClearBuffer, DataBits, FlowControl, Parity, SerialPort, StopBits};
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+// Sky of the simulated receiver: (svId, elevation, azimuth, C/N0), all GPS and used
+const SIM_SATELLITES: [(u8, i8, i16, u8); 8] = [
+    (2, 67, 45, 44),
+    (5, 41, 130, 41),
+    (9, 23, 210, 36),
+    (12, 55, 300, 43),
+    (17, 12, 80, 30),
+    (19, 34, 165, 39),
+    (25, 48, 255, 42),
+    (29, 8, 340, 27),
+];
+
+// qualityInd 7, svUsed, healthy, orbit from ephemeris, ephemeris available
+const SIM_SAT_FLAGS: u32 = 0x07 | 1 << 3 | 1 << 4 | 1 << 8 | 1 << 11;
+
+/*
+    A receiver without hardware, for examples, CI and trying the API out.
+
+    Implements SerialPort, so it can be passed anywhere a port is expected (read_ubx_message,
+    wait_for_message, run_pump ...). Every measurement interval (1 s, set_interval() to
+    speed up tests) it outputs NAV-POSLLH and NAV-SAT for a position moving along a
+    circle. Reads wait up to the port timeout for the next epoch and then fail with
+    TimedOut, like a real port.
+
+    Written CFG messages are acknowledged with ACK-ACK (and otherwise ignored), polls of
+    NAV-POSLLH / NAV-SAT are answered by the next epoch.
+
+    let mut gps = SimulatedGps::circular(center, 50.0, 5.0);
+    gps.set_interval(Duration::from_millis(10));
+    let msg = wait_for_message(&mut gps, 0x01, 0x02, Duration::from_secs(1));
+*/
+pub struct SimulatedGps {
+    center: Position,
+    radius: f64,
+    speed: f64,
+    interval: Duration,
+    #[cfg(feature = "serialport")]
+    baud_rate: u32,
+    timeout: Duration,
+    // SerialPort::bytes_to_read / clear only get &self
+    state: Mutex<SimState>,
+    input: UbxParser,
+}
+
+struct SimState {
+    output: VecDeque<u8>,
+    epoch: u32,
+    next_epoch_at: Instant,
+}
+
+impl SimulatedGps {
+    // Circling center at radius meters, speed m/s, clockwise starting due north of it
+    pub fn circular(center: Position, radius: f64, speed: f64) -> Self {
+        let mut input = UbxParser::new();
+        input.set_frame_timeout(None);
+
+        SimulatedGps {
+            center,
+            radius,
+            speed,
+            interval: Duration::from_secs(1),
+            #[cfg(feature = "serialport")]
+            baud_rate: 9600,
+            timeout: Duration::from_millis(200),
+            state: Mutex::new(SimState {
+                output: VecDeque::new(),
+                epoch: 0,
+                next_epoch_at: Instant::now(),
+            }),
+            input,
+        }
+    }
+
+    // Simulated measurement interval, default 1 s
+    pub fn set_interval(&mut self, interval: Duration) {
+        self.interval = interval;
+    }
+
+    // Position of the simulated receiver after elapsed seconds
+    pub fn position_at(&self, elapsed: f64) -> Position {
+        let angle = if self.radius > 0.0 {
+            self.speed * elapsed / self.radius
+        } else {
+            0.0
+        };
+        let (lat_m, lon_m) = meters_per_degree(self.center.lat);
+
+        Position {
+            lat: self.center.lat + self.radius * angle.cos() / lat_m,
+            lon: self.center.lon + self.radius * angle.sin() / lon_m,
+            ..self.center.clone()
+        }
+    }
+
+    fn i_tow(&self, epoch: u32) -> u32 {
+        (epoch as u128 * self.interval.as_millis() % 604_800_000) as u32
+    }
+
+    fn posllh(&self, epoch: u32) -> Vec<u8> {
+        let elapsed = epoch as f64 * self.interval.as_secs_f64();
+        let pos = self.position_at(elapsed);
+        let height = msl_to_ellipsoid_height(pos.lat, pos.lon, pos.height_msl);
+
+        let mut payload = Vec::with_capacity(28);
+        payload.extend(self.i_tow(epoch).to_le_bytes());
+        payload.extend(((pos.lon * 1e7).round() as i32).to_le_bytes());
+        payload.extend(((pos.lat * 1e7).round() as i32).to_le_bytes());
+        payload.extend(((height * 1000.0).round() as i32).to_le_bytes());
+        payload.extend(((pos.height_msl * 1000.0).round() as i32).to_le_bytes());
+        payload.extend(((pos.horizontal_accuracy * 1000.0) as u32).to_le_bytes());
+        payload.extend(((pos.vertical_accuracy * 1000.0) as u32).to_le_bytes());
+        build_ubx_frame(0x01, 0x02, &payload)
+    }
+
+    fn nav_sat(&self, epoch: u32) -> Vec<u8> {
+        let mut payload = Vec::with_capacity(8 + 12 * SIM_SATELLITES.len());
+        payload.extend(self.i_tow(epoch).to_le_bytes());
+        payload.extend([1, SIM_SATELLITES.len() as u8, 0, 0]);
+        for (sv_id, elevation, azimuth, cno) in SIM_SATELLITES {
+            payload.extend([0, sv_id, cno, elevation as u8]);
+            payload.extend(azimuth.to_le_bytes());
+            payload.extend(0i16.to_le_bytes()); // prRes
+            payload.extend(SIM_SAT_FLAGS.to_le_bytes());
+        }
+        build_ubx_frame(0x01, 0x35, &payload)
+    }
+
+    // Output buffer with every epoch due by now appended. A reader falling behind loses
+    // the missed epochs rather than getting a burst of stale ones.
+    fn state(&self) -> MutexGuard<'_, SimState> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        if now >= state.next_epoch_at {
+            let epoch = state.epoch;
+            let frames = [self.posllh(epoch), self.nav_sat(epoch)].concat();
+            state.output.extend(frames);
+            state.epoch += 1;
+            state.next_epoch_at = (state.next_epoch_at + self.interval).max(now);
+        }
+        state
+    }
+}
+
+impl Read for SimulatedGps {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let mut state = self.state();
+            if !state.output.is_empty() {
+                let n = buf.len().min(state.output.len());
+                for (dst, src) in buf.iter_mut().zip(state.output.drain(..n)) {
+                    *dst = src;
+                }
+                return Ok(n);
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(io::ErrorKind::TimedOut.into());
+            }
+            let wait = state.next_epoch_at.min(deadline) - now;
+            drop(state);
+            std::thread::sleep(wait);
+        }
+    }
+}
+
+impl Write for SimulatedGps {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.input.push(data);
+        while let Some(msg) = self.input.next_message() {
+            // CFG writes are acknowledged, CFG polls (empty payload) ignored
+            if msg.class == 0x06 && !msg.payload.is_empty() {
+                let ack = build_ubx_frame(UBX_CLASS_ACK, UBX_ID_ACK_ACK, &[msg.class, msg.id]);
+                self.state().output.extend(ack);
+            }
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serialport")]
+impl SerialPort for SimulatedGps {
+    fn name(&self) -> Option<String> {
+        Some("simulated".to_string())
+    }
+
+    fn baud_rate(&self) -> serialport::Result<u32> {
+        Ok(self.baud_rate)
+    }
+
+    fn data_bits(&self) -> serialport::Result<DataBits> {
+        Ok(DataBits::Eight)
+    }
+
+    fn flow_control(&self) -> serialport::Result<FlowControl> {
+        Ok(FlowControl::None)
+    }
+
+    fn parity(&self) -> serialport::Result<Parity> {
+        Ok(Parity::None)
+    }
+
+    fn stop_bits(&self) -> serialport::Result<StopBits> {
+        Ok(StopBits::One)
+    }
+
+    fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> serialport::Result<()> {
+        self.baud_rate = baud_rate;
+        Ok(())
+    }
+
+    fn set_data_bits(&mut self, _data_bits: DataBits) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_flow_control(&mut self, _flow_control: FlowControl) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_parity(&mut self, _parity: Parity) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_stop_bits(&mut self, _stop_bits: StopBits) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> serialport::Result<()> {
+        self.timeout = timeout;
+        Ok(())
+    }
+
+    fn write_request_to_send(&mut self, _level: bool) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn write_data_terminal_ready(&mut self, _level: bool) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn read_clear_to_send(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn read_data_set_ready(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn read_ring_indicator(&mut self) -> serialport::Result<bool> {
+        Ok(false)
+    }
+
+    fn read_carrier_detect(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn bytes_to_read(&self) -> serialport::Result<u32> {
+        Ok(self.state().output.len() as u32)
+    }
+
+    fn bytes_to_write(&self) -> serialport::Result<u32> {
+        Ok(0)
+    }
+
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> serialport::Result<()> {
+        if matches!(buffer_to_clear, ClearBuffer::Input | ClearBuffer::All) {
+            self.state().output.clear();
+        }
+        Ok(())
+    }
+
+    fn try_clone(&self) -> serialport::Result<Box<dyn SerialPort>> {
+        Err(serialport::Error::new(
+            serialport::ErrorKind::Unknown,
+            "a simulated receiver cannot be cloned",
+        ))
+    }
+
+    fn set_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn clear_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+}
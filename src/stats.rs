@@ -0,0 +1,315 @@
+use crate::{NavSat, Position, i_tow_delta};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StatRange {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Accumulator {
+    count: usize,
+    min: f64,
+    max: f64,
+    sum: f64,
+}
+
+impl Accumulator {
+    fn add(&mut self, value: f64) {
+        if !value.is_finite() {
+            return;
+        }
+        if self.count == 0 {
+            self.min = value;
+            self.max = value;
+        } else {
+            self.min = self.min.min(value);
+            self.max = self.max.max(value);
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn range(&self) -> Option<StatRange> {
+        (self.count > 0).then(|| StatRange {
+            min: self.min,
+            max: self.max,
+            mean: self.sum / self.count as f64,
+        })
+    }
+}
+
+// End of session summary, a range is None if nothing was fed for it
+#[derive(Debug, Clone)]
+pub struct FixStatsSummary {
+    pub fixes: usize,
+    // meters
+    pub horizontal_accuracy: Option<StatRange>,
+    pub vertical_accuracy: Option<StatRange>,
+    // dBHz, over every tracked satellite of every NAV-SAT fed
+    pub cno: Option<StatRange>,
+}
+
+// Accuracy (and optionally signal strength) statistics over a session
+#[derive(Debug, Clone, Default)]
+pub struct FixStats {
+    fixes: usize,
+    h_acc: Accumulator,
+    v_acc: Accumulator,
+    cno: Accumulator,
+}
+
+impl FixStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, pos: &Position) {
+        self.fixes += 1;
+        self.h_acc.add(pos.horizontal_accuracy);
+        self.v_acc.add(pos.vertical_accuracy);
+    }
+
+    // Satellites with C/N0 0 (not tracked) are skipped
+    pub fn add_nav_sat(&mut self, sat: &NavSat) {
+        for sv in sat.satellites.iter().filter(|sv| sv.cno > 0) {
+            self.cno.add(sv.cno as f64);
+        }
+    }
+
+    pub fn summary(&self) -> FixStatsSummary {
+        FixStatsSummary {
+            fixes: self.fixes,
+            horizontal_accuracy: self.h_acc.range(),
+            vertical_accuracy: self.v_acc.range(),
+            cno: self.cno.range(),
+        }
+    }
+}
+
+// Default noise floor of TrackAccumulator, meters
+pub const DEFAULT_TRACK_NOISE_FLOOR_M: f64 = 3.0;
+
+/*
+    Odometer over a position stream, Haversine segments summed in meters.
+
+    A point closer than the noise floor to the last counted point is ignored and does not
+    move the reference, so jitter while stationary adds nothing but a slow walk is still
+    counted once it clears the floor.
+*/
+#[derive(Debug, Clone)]
+pub struct TrackAccumulator {
+    noise_floor: f64,
+    last: Option<Position>,
+    total: f64,
+}
+
+impl Default for TrackAccumulator {
+    fn default() -> Self {
+        Self::new(DEFAULT_TRACK_NOISE_FLOOR_M)
+    }
+}
+
+impl TrackAccumulator {
+    pub fn new(noise_floor: f64) -> Self {
+        TrackAccumulator {
+            noise_floor,
+            last: None,
+            total: 0.0,
+        }
+    }
+
+    // Returns the running total in meters
+    pub fn add(&mut self, pos: Position) -> f64 {
+        match &self.last {
+            None => self.last = Some(pos),
+            Some(last) => {
+                let segment = last.distance_to(&pos);
+                if segment >= self.noise_floor {
+                    self.total += segment;
+                    self.last = Some(pos);
+                }
+            }
+        }
+        self.total
+    }
+
+    pub fn total(&self) -> f64 {
+        self.total
+    }
+
+    pub fn reset(&mut self) {
+        self.last = None;
+        self.total = 0.0;
+    }
+}
+
+// Default smoothing factor of VerticalSpeedEstimator
+pub const DEFAULT_VERTICAL_SPEED_SMOOTHING: f64 = 0.3;
+
+/*
+    Climb / descent rate from consecutive fixes, for receivers only outputting NAV-POSLLH
+    (NAV-VELNED carries velD directly).
+
+    Each sample's rate is the height_msl delta over the iTOW delta, smoothed exponentially:
+    estimate += smoothing * (rate - estimate), smoothing in (0, 1], 1 meaning none.
+    A sample with the same or an older iTOW than the last one (duplicate or out of
+    order) is ignored, as is one without a height. The end of week rollover is handled.
+*/
+#[derive(Debug, Clone)]
+pub struct VerticalSpeedEstimator {
+    smoothing: f64,
+    last: Option<(f64, u32)>,
+    rate: Option<f64>,
+}
+
+impl Default for VerticalSpeedEstimator {
+    fn default() -> Self {
+        Self::new(DEFAULT_VERTICAL_SPEED_SMOOTHING)
+    }
+}
+
+impl VerticalSpeedEstimator {
+    pub fn new(smoothing: f64) -> Self {
+        VerticalSpeedEstimator {
+            smoothing: smoothing.clamp(f64::MIN_POSITIVE, 1.0),
+            last: None,
+            rate: None,
+        }
+    }
+
+    // Returns the smoothed vertical speed in m/s, positive climbing, None until two
+    // usable samples were fed
+    pub fn add(&mut self, pos: &Position, i_tow: u32) -> Option<f64> {
+        if !pos.height_msl.is_finite() {
+            return self.rate;
+        }
+
+        let Some((last_height, last_i_tow)) = self.last else {
+            self.last = Some((pos.height_msl, i_tow));
+            return None;
+        };
+
+        let dt = i_tow_delta(last_i_tow, i_tow);
+        if dt <= 0 {
+            return self.rate;
+        }
+
+        let rate = (pos.height_msl - last_height) / (dt as f64 / 1000.0);
+        self.last = Some((pos.height_msl, i_tow));
+        self.rate = Some(match self.rate {
+            Some(estimate) => estimate + self.smoothing * (rate - estimate),
+            None => rate,
+        });
+        self.rate
+    }
+
+    pub fn vertical_speed(&self) -> Option<f64> {
+        self.rate
+    }
+
+    pub fn reset(&mut self) {
+        self.last = None;
+        self.rate = None;
+    }
+}
+
+/*
+    Detects whether the antenna has stayed put, e.g. before starting a survey-in or to
+    raise a theft alert on a base station.
+
+    Stationary when the fixes of the last window (which must be fully covered, at least
+    two fixes spanning it) all lie within radius of their mean position. Each fix's own
+    horizontal_accuracy is allowed on top of the radius, so a noisy fix wandering off by
+    less than its reported error does not count as movement. Fixes with an unknown
+    accuracy get no allowance.
+*/
+#[derive(Debug, Clone)]
+pub struct StationarityDetector {
+    radius: f64,
+    window: Duration,
+    fixes: VecDeque<(Instant, Position)>,
+}
+
+impl StationarityDetector {
+    pub fn new(radius: f64, window: Duration) -> Self {
+        StationarityDetector {
+            radius,
+            window,
+            fixes: VecDeque::new(),
+        }
+    }
+
+    pub fn add(&mut self, pos: &Position) {
+        self.add_at(pos, Instant::now());
+    }
+
+    // For replayed or externally timestamped fixes
+    pub fn add_at(&mut self, pos: &Position, at: Instant) {
+        if !pos.lat.is_finite() || !pos.lon.is_finite() {
+            return;
+        }
+        self.fixes.push_back((at, pos.clone()));
+        // keep one fix at or before the window start, so coverage can be checked
+        while let Some(start) = at.checked_sub(self.window)
+            && self.fixes.get(1).is_some_and(|(t, _)| *t <= start)
+        {
+            self.fixes.pop_front();
+        }
+    }
+
+    // Mean position of the window, None without fixes
+    pub fn center(&self) -> Option<Position> {
+        let (_, first) = self.fixes.front()?;
+        let n = self.fixes.len() as f64;
+        // longitudes relative to the first fix, so the mean survives the antimeridian
+        let (lat, dlon) = self.fixes.iter().fold((0.0, 0.0), |(lat, dlon), (_, pos)| {
+            let d = (pos.lon - first.lon + 540.0).rem_euclid(360.0) - 180.0;
+            (lat + pos.lat / n, dlon + d / n)
+        });
+        Some(Position {
+            lat,
+            lon: (first.lon + dlon + 540.0).rem_euclid(360.0) - 180.0,
+            height_msl: first.height_msl,
+            horizontal_accuracy: f64::NAN,
+            vertical_accuracy: f64::NAN,
+        })
+    }
+
+    // Largest distance of a fix from the center minus its accuracy allowance, meters
+    pub fn spread(&self) -> Option<f64> {
+        let center = self.center()?;
+        self.fixes
+            .iter()
+            .map(|(_, pos)| {
+                let allowance = if pos.horizontal_accuracy.is_finite() {
+                    pos.horizontal_accuracy.max(0.0)
+                } else {
+                    0.0
+                };
+                (pos.distance_to(&center) - allowance).max(0.0)
+            })
+            .reduce(f64::max)
+    }
+
+    // The fixes fed so far span the whole window
+    pub fn window_covered(&self) -> bool {
+        match (self.fixes.front(), self.fixes.back()) {
+            (Some((first, _)), Some((last, _))) if self.fixes.len() >= 2 => {
+                last.duration_since(*first) >= self.window
+            }
+            _ => false,
+        }
+    }
+
+    pub fn is_stationary(&self) -> bool {
+        self.window_covered() && self.spread().is_some_and(|spread| spread <= self.radius)
+    }
+
+    pub fn reset(&mut self) {
+        self.fixes.clear();
+    }
+}
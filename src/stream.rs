@@ -0,0 +1,82 @@
+use crate::{DecodedMessage, UbxParser};
+use futures_core::Stream;
+use futures_io::AsyncRead;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/*
+    Async adapter yielding decoded messages, the async counterpart of run_pump
+    (futures feature).
+
+    use futures::StreamExt;
+    let mut stream = message_stream(port);
+    while let Some(msg) = stream.next().await { ... }
+
+    port is any futures AsyncRead, a tokio serial port goes through
+    tokio_util::compat (.compat()). Read timeouts are skipped, the stream ends at end
+    of input or on any other I/O error, take_error() then returns the error.
+*/
+pub struct MessageStream<R> {
+    reader: R,
+    parser: UbxParser,
+    buf: Vec<u8>,
+    done: bool,
+    error: Option<io::Error>,
+}
+
+pub fn message_stream<R: AsyncRead + Unpin>(reader: R) -> MessageStream<R> {
+    MessageStream {
+        reader,
+        parser: UbxParser::new(),
+        buf: vec![0; 1024],
+        done: false,
+        error: None,
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for MessageStream<R> {
+    type Item = DecodedMessage;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<DecodedMessage>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(msg) = this.parser.next_decoded() {
+                return Poll::Ready(Some(msg));
+            }
+            if this.done {
+                return Poll::Ready(None);
+            }
+
+            match Pin::new(&mut this.reader).poll_read(cx, &mut this.buf) {
+                Poll::Ready(Ok(0)) => this.done = true,
+                Poll::Ready(Ok(n)) => this.parser.push(&this.buf[..n]),
+                Poll::Ready(Err(e))
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::TimedOut | io::ErrorKind::Interrupted
+                    ) => {}
+                Poll::Ready(Err(e)) => {
+                    this.error = Some(e);
+                    this.done = true;
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl<R> MessageStream<R> {
+    // The I/O error that ended the stream, if any
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    pub fn parser(&self) -> &UbxParser {
+        &self.parser
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
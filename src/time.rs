@@ -0,0 +1,290 @@
+use crate::{CfgRate, DecodedMessage, TimestampedMessage};
+#[cfg(feature = "chrono")]
+use chrono::{NaiveDate, NaiveDateTime, TimeDelta};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// GPS epoch, 1980-01-06 00:00:00 UTC, as seconds since the Unix epoch
+pub const GPS_EPOCH_UNIX_SECS: u64 = 315_964_800;
+
+pub const SECONDS_PER_WEEK: u64 = 604_800;
+
+/*
+    GPS time is a continuous count of weeks and milliseconds (iTOW) since the GPS epoch.
+    It does not apply leap seconds, so UTC = GPS - leap_seconds (18 s since 2017).
+
+    The result is a std SystemTime, with the chrono feature gps_time_to_naive_utc gives
+    the same instant as a chrono NaiveDateTime for calendar fields.
+
+    Week rollover: the legacy navigation message only carries 10 bits of week number,
+    so it wraps every 1024 weeks (1999-08-22, 2019-04-07). The u-blox 7 firmware resolves
+    the full week from its build date, old firmware may therefore report a week that is
+    1024 weeks too small. The week is taken as is here.
+*/
+pub fn gps_time_to_utc(week: u16, itow_ms: u32, leap_seconds: i8) -> SystemTime {
+    let gps_ms = (GPS_EPOCH_UNIX_SECS + week as u64 * SECONDS_PER_WEEK) as i64 * 1000
+        + itow_ms as i64
+        - leap_seconds as i64 * 1000;
+
+    UNIX_EPOCH + Duration::from_millis(gps_ms.max(0) as u64)
+}
+
+// GPS epoch as a chrono NaiveDateTime (UTC)
+#[cfg(feature = "chrono")]
+pub const GPS_EPOCH_NAIVE: NaiveDateTime = NaiveDate::from_ymd_opt(1980, 1, 6)
+    .unwrap()
+    .and_hms_opt(0, 0, 0)
+    .unwrap();
+
+// gps_time_to_utc as a chrono NaiveDateTime (UTC). Named apart from gps_time_to_utc,
+// which keeps returning SystemTime so the crate builds without chrono.
+#[cfg(feature = "chrono")]
+pub fn gps_time_to_naive_utc(week: u16, itow_ms: u32, leap_seconds: i8) -> NaiveDateTime {
+    GPS_EPOCH_NAIVE + TimeDelta::weeks(week as i64) + TimeDelta::milliseconds(itow_ms as i64)
+        - TimeDelta::seconds(leap_seconds as i64)
+}
+
+// Weeks in one cycle of the 10 bit GPS week number
+pub const GPS_WEEK_CYCLE: u64 = 1024;
+
+/*
+    Old u-blox 7 firmware predates the April 2019 week rollover and may resolve the
+    10 bit broadcast week into the previous 1024 week cycle, reporting dates around
+    August 1999 / 2000.
+
+    The time is moved forward by whole 1024 week cycles until it is no earlier than
+    reference. Pick a reference you know the real date is after, e.g. the build date
+    of the application, but less than ~19.6 years before the true date.
+*/
+pub fn correct_week_rollover(dt: SystemTime, reference: SystemTime) -> SystemTime {
+    let cycle = Duration::from_secs(GPS_WEEK_CYCLE * SECONDS_PER_WEEK);
+    let mut corrected = dt;
+    while corrected < reference {
+        corrected += cycle;
+    }
+    corrected
+}
+
+// correct_week_rollover for chrono NaiveDateTime (UTC)
+#[cfg(feature = "chrono")]
+pub fn correct_week_rollover_naive(dt: NaiveDateTime, reference: NaiveDateTime) -> NaiveDateTime {
+    let cycle = TimeDelta::weeks(GPS_WEEK_CYCLE as i64);
+    let mut corrected = dt;
+    while corrected < reference {
+        corrected += cycle;
+    }
+    corrected
+}
+
+// Days since 1970-01-01 of a proleptic Gregorian date (Howard Hinnant's days_from_civil)
+pub(crate) fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year } as i64;
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let m = month as i64;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+pub(crate) fn utc_from_fields(
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    min: u8,
+    sec: u8,
+    nano: i32,
+) -> SystemTime {
+    let days = days_from_civil(year as i32, month as u32, day as u32);
+    let secs = days * 86_400 + hour as i64 * 3600 + min as i64 * 60 + sec as i64;
+    let nanos = (secs as i128 * 1_000_000_000 + nano as i128).max(0) as u64;
+    UNIX_EPOCH + Duration::from_nanos(nanos)
+}
+
+// Calendar fields of a UTC time (year, month, day, hour, minute, second, millisecond),
+// the inverse of days_from_civil
+pub(crate) fn utc_fields(t: SystemTime) -> (i32, u32, u32, u32, u32, u32, u32) {
+    let (secs, millis) = match t.duration_since(UNIX_EPOCH) {
+        Ok(d) => (d.as_secs() as i64, d.subsec_millis()),
+        Err(e) => {
+            let d = e.duration();
+            let ms = d.as_millis() as i64;
+            (-(ms + 999) / 1000, ((1000 - ms % 1000) % 1000) as u32)
+        }
+    };
+
+    let days = secs.div_euclid(86_400);
+    let sod = secs.rem_euclid(86_400) as u32;
+
+    let z = days + 719_468;
+    let era = if z >= 0 { z } else { z - 146_096 } / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = (yoe + era * 400 + i64::from(month <= 2)) as i32;
+
+    (
+        year,
+        month,
+        day,
+        sod / 3600,
+        sod / 60 % 60,
+        sod % 60,
+        millis,
+    )
+}
+
+// ISO 8601 UTC with millisecond precision, e.g. 2025-03-01T12:00:00.000Z
+pub fn format_utc(t: SystemTime) -> String {
+    let (year, month, day, hour, min, sec, millis) = utc_fields(t);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year, month, day, hour, min, sec, millis
+    )
+}
+
+const MS_PER_WEEK: i64 = SECONDS_PER_WEEK as i64 * 1000;
+
+// current - previous iTOW in ms, across the end of week rollover
+pub(crate) fn i_tow_delta(previous: u32, current: u32) -> i64 {
+    let delta = current as i64 - previous as i64;
+    if delta < -MS_PER_WEEK / 2 {
+        delta + MS_PER_WEEK
+    } else {
+        delta
+    }
+}
+
+/*
+    Watches iTOW across messages for receiver clock steps.
+
+    Messages of one epoch share their iTOW, consecutive epochs advance by the measurement
+    interval. A step backwards, or forward by more than max_interval, is a jump: data
+    timestamped by iTOW around it is out of order or has a gap. The end of week rollover
+    (604799999 -> 0) is not a jump.
+
+    A lost epoch looks like a forward jump, allow for that in max_interval (e.g. 2.5x the
+    measurement interval) if only steps are of interest.
+*/
+#[derive(Debug, Clone)]
+pub struct ClockMonitor {
+    max_interval: i64,
+    last_i_tow: Option<u32>,
+    last_jump: Option<i64>,
+    jumps: usize,
+}
+
+impl ClockMonitor {
+    pub fn new(max_interval: Duration) -> Self {
+        ClockMonitor {
+            max_interval: max_interval.as_millis() as i64,
+            last_i_tow: None,
+            last_jump: None,
+            jumps: 0,
+        }
+    }
+
+    // Feeds an iTOW (ms), returns the delta in ms if it is a jump
+    pub fn observe(&mut self, i_tow: u32) -> Option<i64> {
+        let previous = self.last_i_tow.replace(i_tow)?;
+        let delta = i_tow_delta(previous, i_tow);
+
+        if delta < 0 || delta > self.max_interval {
+            self.last_jump = Some(delta);
+            self.jumps += 1;
+            return Some(delta);
+        }
+        None
+    }
+
+    // Feeds the iTOW of a decoded message, messages without one are ignored
+    pub fn process(&mut self, msg: &DecodedMessage) -> Option<i64> {
+        self.observe(msg.i_tow()?)
+    }
+
+    // Delta (ms) of the most recent jump
+    pub fn last_jump(&self) -> Option<i64> {
+        self.last_jump
+    }
+
+    pub fn jumps(&self) -> usize {
+        self.jumps
+    }
+}
+
+/*
+    When the next navigation solution is due, for periodic read loops sleeping until just
+    before it instead of polling the port.
+
+    Feed the receive time of each solution (one message per epoch, e.g. NAV-PVT or
+    NAV-POSLLH), next_expected() is that plus the solution interval. Missed epochs are
+    skipped over so the estimate never lies in the past, and the receive times absorb
+    transmission latency and drift between the receiver and host clocks.
+
+    let mut schedule = FixSchedule::from_cfg_rate(&get_measurement_rate(&mut *port)?);
+    loop {
+        sleep(schedule.time_until_next().saturating_sub(Duration::from_millis(20)));
+        if let Some(msg) = parser.next_timestamped() {
+            schedule.observe(msg.received_at);
+        }
+    }
+*/
+#[derive(Debug, Clone)]
+pub struct FixSchedule {
+    interval: Duration,
+    last: Option<Instant>,
+}
+
+impl FixSchedule {
+    pub fn new(interval: Duration) -> Self {
+        FixSchedule {
+            interval,
+            last: None,
+        }
+    }
+
+    // Solution interval measRate * navRate of a polled CFG-RATE
+    pub fn from_cfg_rate(rate: &CfgRate) -> Self {
+        let ms = rate.meas_rate as u64 * rate.nav_rate.max(1) as u64;
+        Self::new(Duration::from_millis(ms))
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    // Receive time of a solution
+    pub fn observe(&mut self, received_at: Instant) {
+        self.last = Some(received_at);
+    }
+
+    pub fn observe_message(&mut self, msg: &TimestampedMessage) {
+        self.observe(msg.received_at);
+    }
+
+    // Now if no solution was observed yet
+    pub fn next_expected(&self) -> Instant {
+        let now = Instant::now();
+        let Some(last) = self.last else {
+            return now;
+        };
+        if self.interval.is_zero() {
+            return now;
+        }
+
+        let mut next = last + self.interval;
+        if next < now {
+            let missed = (now - next).as_nanos() / self.interval.as_nanos() + 1;
+            next += self.interval * missed as u32;
+        }
+        next
+    }
+
+    pub fn time_until_next(&self) -> Duration {
+        self.next_expected()
+            .saturating_duration_since(Instant::now())
+    }
+}
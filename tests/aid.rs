@@ -0,0 +1,77 @@
+use std::time::{Duration, UNIX_EPOCH};
+use ublox7::{AidIniBuilder, Position};
+
+fn u16_at(payload: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes(payload[offset..offset + 2].try_into().unwrap())
+}
+
+fn u32_at(payload: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(payload[offset..offset + 4].try_into().unwrap())
+}
+
+fn i32_at(payload: &[u8], offset: usize) -> i32 {
+    i32::from_le_bytes(payload[offset..offset + 4].try_into().unwrap())
+}
+
+fn position(height_msl: f64) -> Position {
+    Position {
+        lat: 47.3655900,
+        lon: -8.5249970,
+        height_msl,
+        horizontal_accuracy: 1.0,
+        vertical_accuracy: 1.0,
+    }
+}
+
+// 2024-03-15 12:34:56.25 UTC
+fn utc() -> std::time::SystemTime {
+    UNIX_EPOCH + Duration::from_millis(1_710_506_096_250)
+}
+
+#[test]
+fn aid_ini_position_and_time_payload() {
+    let payload = AidIniBuilder::new()
+        .position(&position(400.0), 5_000.0)
+        .time(utc(), Duration::from_millis(2_500))
+        .payload();
+
+    assert_eq!(i32_at(&payload, 0), 473_655_900);
+    assert_eq!(i32_at(&payload, 4), -85_249_970);
+    assert_eq!(u32_at(&payload, 12), 500_000); // cm
+    assert_eq!(u16_at(&payload, 18), 2403); // YYMM
+    assert_eq!(u32_at(&payload, 20), 15_123_456); // DDHHMMSS
+    assert_eq!(i32_at(&payload, 24), 250_000_000); // ns
+    assert_eq!(u32_at(&payload, 28), 2_500); // ms
+    assert_eq!(u32_at(&payload, 32), 0);
+    // pos | time | lla | utc
+    assert_eq!(u32_at(&payload, 44), 0x0423);
+}
+
+#[test]
+fn aid_ini_unknown_height_sets_alt_invalid() {
+    let payload = AidIniBuilder::new()
+        .position(&position(f64::NAN), 100.0)
+        .payload();
+
+    assert_eq!(i32_at(&payload, 8), 0);
+    // pos | lla | altInv
+    assert_eq!(u32_at(&payload, 44), 0x0061);
+}
+
+#[test]
+fn aid_ini_clock_drift_and_frame() {
+    let builder = AidIniBuilder::new().clock_drift(-1_200, 50);
+    let payload = builder.payload();
+    assert_eq!(i32_at(&payload, 36), -1_200);
+    assert_eq!(u32_at(&payload, 40), 50);
+    assert_eq!(u32_at(&payload, 44), 0x0004);
+
+    let frame = builder.build();
+    assert_eq!(frame[..6], [0xB5, 0x62, 0x0B, 0x01, 48, 0]);
+    assert_eq!(frame[6..54], payload);
+}
+
+#[test]
+fn aid_ini_empty_flags_nothing() {
+    assert_eq!(AidIniBuilder::new().payload(), [0; 48]);
+}
@@ -0,0 +1,54 @@
+use std::io::{Cursor, ErrorKind};
+use std::time::{Duration, UNIX_EPOCH};
+use ublox7::{BinLogReader, BinLogWriter, UbxMessage};
+
+fn message(id: u8, payload: &[u8]) -> UbxMessage {
+    UbxMessage {
+        class: 0x01,
+        id,
+        payload: payload.to_vec(),
+    }
+}
+
+fn log_of(messages: &[UbxMessage]) -> Vec<u8> {
+    let mut writer = BinLogWriter::new(Vec::new()).unwrap();
+    for (i, msg) in messages.iter().enumerate() {
+        let time = UNIX_EPOCH + Duration::from_micros(1_700_000_000_000_000 + i as u64);
+        writer.write_message_at(time, msg).unwrap();
+    }
+    writer.finish().unwrap()
+}
+
+#[test]
+fn binlog_round_trip_drops_truncated_last_record() {
+    let messages = [
+        message(0x02, &[1; 28]),
+        message(0x03, &[2; 16]),
+        message(0x07, &[3; 92]),
+    ];
+    let mut log = log_of(&messages);
+    log.truncate(log.len() - 10);
+
+    let mut reader = BinLogReader::new(Cursor::new(log)).unwrap();
+    let read: Vec<_> = reader.by_ref().collect();
+    assert!(reader.take_error().is_none());
+
+    assert_eq!(read.len(), 2);
+    for (i, (time, msg)) in read.iter().enumerate() {
+        let expected = UNIX_EPOCH + Duration::from_micros(1_700_000_000_000_000 + i as u64);
+        assert_eq!(*time, expected);
+        assert_eq!((msg.class, msg.id), (messages[i].class, messages[i].id));
+        assert_eq!(msg.payload, messages[i].payload);
+    }
+}
+
+#[test]
+fn binlog_rejects_impossible_record_length() {
+    let mut log = log_of(&[message(0x02, &[1; 28])]);
+    log.extend(u32::MAX.to_le_bytes());
+    log.extend([0; 16]);
+
+    let mut reader = BinLogReader::new(Cursor::new(log)).unwrap();
+    assert_eq!(reader.by_ref().count(), 1);
+    assert_eq!(reader.take_error().unwrap().kind(), ErrorKind::InvalidData);
+}
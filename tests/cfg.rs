@@ -0,0 +1,50 @@
+use ublox7::{CfgBuilder, DatumParams, cfg_set_payload, parse_cfg_dat};
+
+// CFG-DAT poll answer of a receiver set to ED50: datumNum -1 (user), "USER", parameters
+fn cfg_dat_answer(datum: &DatumParams) -> Vec<u8> {
+    let mut answer = Vec::with_capacity(52);
+    answer.extend(0xFFFFu16.to_le_bytes());
+    answer.extend(b"USER\0\0");
+    answer.extend(datum.major_axis.to_le_bytes());
+    answer.extend(datum.flattening.to_le_bytes());
+    for value in [
+        datum.dx,
+        datum.dy,
+        datum.dz,
+        datum.rot_x,
+        datum.rot_y,
+        datum.rot_z,
+        datum.scale,
+    ] {
+        answer.extend(value.to_le_bytes());
+    }
+    answer
+}
+
+#[test]
+fn cfg_dat_answer_restores_as_set_layout() {
+    let datum = DatumParams::ED50;
+    let answer = cfg_dat_answer(&datum);
+    assert_eq!(answer.len(), 52);
+    assert_eq!(parse_cfg_dat(&answer).unwrap().params, datum);
+
+    // as configure_datum builds it
+    let expected = CfgBuilder::new(0x06)
+        .set_f64_le(0, datum.major_axis)
+        .set_f64_le(8, datum.flattening)
+        .set_f32_le(16, datum.dx)
+        .set_f32_le(20, datum.dy)
+        .set_f32_le(24, datum.dz)
+        .set_f32_le(28, datum.rot_x)
+        .set_f32_le(32, datum.rot_y)
+        .set_f32_le(36, datum.rot_z)
+        .set_f32_le(40, datum.scale);
+    assert_eq!(cfg_set_payload(0x06, &answer), expected.payload());
+    assert_eq!(cfg_set_payload(0x06, &answer).len(), 44);
+}
+
+#[test]
+fn other_cfg_answers_restore_unchanged() {
+    let rate = [0xC8, 0x00, 0x01, 0x00, 0x01, 0x00];
+    assert_eq!(cfg_set_payload(0x08, &rate), rate);
+}
@@ -0,0 +1,12 @@
+use ublox7::Position;
+
+// Fix at lat / lon with 1 m accuracy, for tests that only care about the coordinates
+pub fn position(lat: f64, lon: f64) -> Position {
+    Position {
+        lat,
+        lon,
+        height_msl: 0.0,
+        horizontal_accuracy: 1.0,
+        vertical_accuracy: 1.0,
+    }
+}
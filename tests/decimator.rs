@@ -0,0 +1,38 @@
+use std::time::{Duration, Instant};
+use ublox7::Decimator;
+
+#[test]
+fn every_nth_keeps_first_of_each_group() {
+    let kept: Vec<u32> = Decimator::every_nth(3).wrap(0..10).collect();
+    assert_eq!(kept, [0, 3, 6, 9]);
+
+    let all: Vec<u32> = Decimator::every_nth(0).wrap(0..3).collect();
+    assert_eq!(all, [0, 1, 2]);
+}
+
+#[test]
+fn per_interval_does_not_drift_with_jitter() {
+    let start = Instant::now();
+    let mut decimator = Decimator::per_interval(Duration::from_secs(1));
+
+    // 10 Hz with +-5 ms jitter for 5 s
+    let passed: Vec<u64> = (0..50u64)
+        .filter(|i| {
+            let jitter = if i % 2 == 0 { 5 } else { 0 };
+            decimator.accept_at(start + Duration::from_millis(i * 100 + jitter))
+        })
+        .collect();
+    assert_eq!(passed, [0, 10, 20, 30, 40]);
+}
+
+#[test]
+fn per_interval_restarts_after_a_gap() {
+    let start = Instant::now();
+    let mut decimator = Decimator::per_interval(Duration::from_secs(1));
+    let at = |ms| start + Duration::from_millis(ms);
+
+    assert!(decimator.accept_at(at(0)));
+    assert!(decimator.accept_at(at(5_300)));
+    assert!(!decimator.accept_at(at(6_000)));
+    assert!(decimator.accept_at(at(6_300)));
+}
@@ -0,0 +1,111 @@
+use ublox7::{
+    BufferUsage, DecodedMessage, Decoder, MonTxBuf, Position, UbxMessage, build_ubx_frame,
+    decode_all, decode_message,
+};
+
+fn message(class: u8, id: u8, payload: &[u8]) -> UbxMessage {
+    UbxMessage {
+        class,
+        id,
+        payload: payload.to_vec(),
+    }
+}
+
+// Proprietary message for the registration tests: a little endian u16 counter
+#[derive(Debug, PartialEq)]
+struct Counter(u16);
+
+fn parse_counter(payload: &[u8]) -> Option<Counter> {
+    Some(Counter(u16::from_le_bytes(
+        payload.get(..2)?.try_into().ok()?,
+    )))
+}
+
+#[test]
+fn custom_message_registers_and_unregisters() {
+    let mut decoder = Decoder::new();
+    let msg = message(0xF0, 0x01, &[0x34, 0x12]);
+    assert!(!decoder.is_registered(0xF0, 0x01));
+    assert!(decoder.decode(&msg).is_none());
+
+    decoder.register(0xF0, 0x01, parse_counter);
+    assert!(decoder.is_registered(0xF0, 0x01));
+    let decoded = decoder.decode(&msg).unwrap();
+    assert_eq!(decoded.downcast_ref::<Counter>(), Some(&Counter(0x1234)));
+    assert!(decoded.downcast_ref::<Position>().is_none());
+    // payload the parser rejects
+    assert!(decoder.decode(&message(0xF0, 0x01, &[0x34])).is_none());
+
+    decoder.unregister(0xF0, 0x01);
+    assert!(!decoder.is_registered(0xF0, 0x01));
+    assert!(decoder.decode(&msg).is_none());
+}
+
+#[test]
+fn register_replaces_built_in_parser() {
+    let mut decoder = Decoder::new();
+    let posllh = message(0x01, 0x02, &[0; 28]);
+    assert!(decoder.decode(&posllh).unwrap().is::<Position>());
+
+    decoder.register(0x01, 0x02, parse_counter);
+    assert!(decoder.decode(&posllh).unwrap().is::<Counter>());
+    assert!(!Decoder::empty().is_registered(0x01, 0x02));
+}
+
+#[test]
+fn mon_buffers_decode_in_both_tables() {
+    let mut txbuf = [0u8; 28];
+    txbuf[0] = 0x10; // DDC pending
+    txbuf[24] = 42; // total usage
+    let rxbuf = &txbuf[..24];
+
+    let decoder = Decoder::new();
+    let rx = decoder.decode(&message(0x0A, 0x07, rxbuf)).unwrap();
+    assert_eq!(rx.downcast_ref::<BufferUsage>().unwrap().pending[0], 0x10);
+    let tx = decoder.decode(&message(0x0A, 0x08, &txbuf)).unwrap();
+    assert_eq!(tx.downcast_ref::<MonTxBuf>().unwrap().total_usage, 42);
+
+    match decode_message(message(0x0A, 0x07, rxbuf)) {
+        DecodedMessage::MonRxBuf(rx) => assert_eq!(rx.pending[0], 0x10),
+        other => panic!("unexpected {:?}", other),
+    }
+    match decode_message(message(0x0A, 0x08, &txbuf)) {
+        DecodedMessage::MonTxBuf(tx) => assert_eq!(tx.total_usage, 42),
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn decode_all_skips_garbage_and_truncated_frames() {
+    let mut posllh = vec![0u8; 28];
+    posllh[8..12].copy_from_slice(&473_000_000i32.to_le_bytes());
+
+    let mut data = vec![0x00, 0xB5, 0x13, 0x62, 0xFF];
+    data.extend(build_ubx_frame(0x05, 0x01, &[0x06, 0x01]));
+    data.extend(build_ubx_frame(0x01, 0x02, &posllh));
+    data.extend(build_ubx_frame(0x7F, 0x42, &[1, 2, 3]));
+    let truncated = build_ubx_frame(0x01, 0x02, &posllh);
+    data.extend(&truncated[..truncated.len() - 3]);
+
+    let messages = decode_all(&data);
+    assert_eq!(messages.len(), 3, "{:?}", messages);
+    assert!(matches!(
+        messages[0],
+        DecodedMessage::Ack {
+            cls: 0x06,
+            id: 0x01,
+            accepted: true
+        }
+    ));
+    match &messages[1] {
+        DecodedMessage::Position(pos) => assert!((pos.lat - 47.3).abs() < 1e-9),
+        other => panic!("unexpected {:?}", other),
+    }
+    match &messages[2] {
+        DecodedMessage::Raw(raw) => {
+            assert_eq!((raw.class, raw.id), (0x7F, 0x42));
+            assert_eq!(raw.payload, [1, 2, 3]);
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+}
@@ -0,0 +1,69 @@
+#[cfg(feature = "serialport")]
+mod common;
+
+use ublox7::{
+    DecodedMessage, UBX_SYNC_1, UBX_SYNC_2, UbxHeader, UbxParser, build_ubx_frame, decode_all,
+};
+
+// NAV-POSLLH poll request as sent by main.rs: header, zero length, checksum
+const POLL_FRAME: [u8; 8] = [UBX_SYNC_1, UBX_SYNC_2, 0x01, 0x02, 0x00, 0x00, 0x03, 0x0A];
+
+#[test]
+fn zero_length_frame_is_eight_bytes() {
+    let frame = build_ubx_frame(0x01, 0x02, &[]);
+    assert_eq!(frame, POLL_FRAME);
+
+    let header = UbxHeader::parse(&frame).unwrap();
+    assert_eq!(header.length, 0);
+}
+
+#[test]
+fn zero_length_frame_parses_with_empty_payload() {
+    let mut parser = UbxParser::new();
+    parser.push(&POLL_FRAME);
+
+    let msg = parser.next_message().unwrap();
+    assert_eq!((msg.class, msg.id), (0x01, 0x02));
+    assert!(msg.payload.is_empty());
+    assert_eq!(parser.buffered(), 0);
+}
+
+#[test]
+fn zero_length_frame_waits_for_checksum() {
+    let mut parser = UbxParser::new();
+    parser.push(&POLL_FRAME[..7]);
+    assert!(parser.next_message().is_none());
+
+    parser.push(&POLL_FRAME[7..]);
+    assert!(parser.next_message().unwrap().payload.is_empty());
+}
+
+#[test]
+fn zero_length_frame_with_bad_checksum_is_dropped() {
+    let mut frame = POLL_FRAME;
+    frame[7] ^= 0xFF;
+    assert!(decode_all(&frame).is_empty());
+}
+
+#[test]
+fn back_to_back_zero_length_frames() {
+    let mut data = POLL_FRAME.to_vec();
+    data.extend_from_slice(&POLL_FRAME);
+
+    let messages = decode_all(&data);
+    assert_eq!(messages.len(), 2);
+    for msg in messages {
+        match msg {
+            DecodedMessage::Raw(raw) => assert!(raw.payload.is_empty()),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
+
+#[cfg(feature = "serialport")]
+#[test]
+fn send_rejects_payload_longer_than_length_field() {
+    let mut gps = ublox7::SimulatedGps::circular(common::position(47.0, 8.0), 10.0, 1.0);
+    let err = ublox7::send_ubx_command(&mut gps, 0x06, 0x00, &[0; 65536]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
@@ -0,0 +1,26 @@
+mod common;
+
+use common::position;
+
+#[test]
+fn geohash_matches_reference() {
+    // Jutland, the example of the original geohash.org announcement
+    assert_eq!(position(57.64911, 10.40744).to_geohash(11), "u4pruydqqvj");
+    assert_eq!(position(42.6, -5.6).to_geohash(5), "ezs42");
+    assert_eq!(position(0.0, 0.0).to_geohash(4), "s000");
+    assert_eq!(position(-90.0, -180.0).to_geohash(3), "000");
+    assert_eq!(position(90.0, 180.0).to_geohash(3), "bpb");
+}
+
+#[test]
+fn geohash_prefix_is_the_coarser_cell() {
+    let pos = position(57.64911, 10.40744);
+    assert!(pos.to_geohash(11).starts_with(&pos.to_geohash(6)));
+}
+
+#[test]
+fn geohash_precision_is_clamped() {
+    let pos = position(57.64911, 10.40744);
+    assert_eq!(pos.to_geohash(0), "u");
+    assert_eq!(pos.to_geohash(20).len(), 12);
+}
@@ -0,0 +1,59 @@
+use ublox7::parse_nav_timels;
+
+// NAV-TIMELS with both validity flags set and the given leap second event day of week
+fn timels(ls_event_day: u16) -> [u8; 24] {
+    let mut payload = [0u8; 24];
+    payload[9] = 18; // current leap seconds
+    payload[16..18].copy_from_slice(&1930u16.to_le_bytes());
+    payload[18..20].copy_from_slice(&ls_event_day.to_le_bytes());
+    payload[23] = 0x03;
+    payload
+}
+
+#[test]
+fn lsevent_day_out_of_range_is_none() {
+    for day in [0, 8, 51, u16::MAX] {
+        let ls = parse_nav_timels(&timels(day)).unwrap();
+        assert!(ls.date_of_lsevent().is_none(), "day {day}");
+    }
+    for day in 1..=7 {
+        assert!(
+            parse_nav_timels(&timels(day))
+                .unwrap()
+                .date_of_lsevent()
+                .is_some()
+        );
+    }
+}
+
+#[cfg(feature = "serialport")]
+#[test]
+fn device_report_counts_used_tracked_and_visible_satellites() {
+    use ublox7::{DeviceReport, GnssId, NavSat, SatInfo};
+
+    let sv = |sv_id, cno, flags| SatInfo {
+        gnss_id: GnssId::Gps,
+        sv_id,
+        cno,
+        elevation: 45,
+        azimuth: 90,
+        pr_res: 0.0,
+        flags,
+    };
+    let report = DeviceReport {
+        // used, tracked only, and two listed from the almanac without signal
+        satellites: Some(NavSat {
+            i_tow: 0,
+            version: 1,
+            satellites: vec![sv(1, 40, 0x08), sv(2, 25, 0), sv(3, 0, 0), sv(4, 0, 0)],
+        }),
+        ..Default::default()
+    };
+
+    let text = report.to_string();
+    assert!(
+        text.contains("Satellites: 1 used / 2 tracked / 4 visible"),
+        "{}",
+        text
+    );
+}
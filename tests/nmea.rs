@@ -0,0 +1,80 @@
+mod common;
+
+use common::position;
+use std::collections::VecDeque;
+use std::io::{self, BufReader, Read};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use ublox7::{Bearing, build_ubx_frame, gga_sentence, nmea_to_positions, rmc_sentence};
+
+// Reader replaying a script of reads like a serial port, None is a read timeout
+struct ScriptedPort(VecDeque<Option<Vec<u8>>>);
+
+impl Read for ScriptedPort {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.0.pop_front() {
+            Some(Some(data)) => {
+                buf[..data.len()].copy_from_slice(&data);
+                Ok(data.len())
+            }
+            Some(None) => Err(io::ErrorKind::TimedOut.into()),
+            None => Ok(0),
+        }
+    }
+}
+
+fn epoch(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(1_700_000_000 + secs)
+}
+
+#[test]
+fn nmea_positions_survive_timeouts_and_binary_lines() {
+    let first = gga_sentence(&position(47.5, 8.25), epoch(0), 1, 8, 0.9);
+    let rmc = rmc_sentence(
+        &position(47.5, 8.25),
+        epoch(0),
+        true,
+        0.0,
+        Bearing::new(0.0),
+    );
+    let second = gga_sentence(&position(47.6, 8.5), epoch(1), 1, 8, 0.9);
+    let third = gga_sentence(&position(47.7, 8.75), epoch(2), 1, 8, 0.9);
+
+    // A NAV-POSLLH frame in front of the second GGA, non-UTF-8 and without a newline
+    let mut binary = build_ubx_frame(0x01, 0x02, &[0xFF; 28]);
+    binary.extend_from_slice(second.as_bytes());
+
+    let port = ScriptedPort(VecDeque::from([
+        Some(first.as_bytes().to_vec()),
+        Some(rmc.as_bytes()[..10].to_vec()),
+        None,
+        Some(rmc.as_bytes()[10..].to_vec()),
+        None,
+        None,
+        Some(binary),
+        Some(b"\xFF\xFE garbage\r\n".to_vec()),
+        None,
+        Some(third.as_bytes().to_vec()),
+    ]));
+
+    let lats: Vec<f64> = nmea_to_positions(BufReader::new(port))
+        .map(|pos| pos.lat)
+        .collect();
+    assert_eq!(lats.len(), 3);
+    for (lat, expected) in lats.iter().zip([47.5, 47.6, 47.7]) {
+        assert!((lat - expected).abs() < 1e-6, "{lat} != {expected}");
+    }
+}
+
+#[test]
+fn nmea_positions_end_on_fatal_read_error() {
+    struct Broken;
+    impl Read for Broken {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+    }
+
+    let gga = gga_sentence(&position(47.5, 8.25), epoch(0), 1, 8, 0.9);
+    let reader = gga.as_bytes().chain(Broken);
+    assert_eq!(nmea_to_positions(BufReader::new(reader)).count(), 1);
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use common::position;
+
+// (lat, lon, length, code) from the Open Location Code reference test data
+const REFERENCE: [(f64, f64, usize, &str); 12] = [
+    (20.375, 2.775, 6, "7FG49Q00+"),
+    (20.3700625, 2.7821875, 10, "7FG49QCJ+2V"),
+    (20.3701125, 2.782234375, 11, "7FG49QCJ+2VX"),
+    (47.0000625, 8.0000625, 10, "8FVC2222+22"),
+    (-41.2730625, 174.7859375, 10, "4VCPPQGP+Q9"),
+    (0.5, -179.5, 4, "62G20000+"),
+    (-89.5, -179.5, 4, "22220000+"),
+    (20.5, 2.5, 4, "7FG40000+"),
+    (-89.9999375, -179.9999375, 10, "22222222+22"),
+    (0.5, 179.5, 4, "6VGX0000+"),
+    (1.0, 1.0, 11, "6FH32222+222"),
+    (47.365590, 8.524997, 10, "8FVC9G8F+6X"),
+];
+
+#[test]
+fn plus_codes_match_reference() {
+    for (lat, lon, length, code) in REFERENCE {
+        assert_eq!(
+            position(lat, lon).to_plus_code(length),
+            code,
+            "{lat}, {lon}"
+        );
+    }
+}
+
+#[test]
+fn plus_code_clips_latitude_and_normalizes_longitude() {
+    assert_eq!(position(90.0, 1.0).to_plus_code(4), "CFX30000+");
+    assert_eq!(position(92.0, 1.0).to_plus_code(4), "CFX30000+");
+    assert_eq!(position(1.0, 181.0).to_plus_code(4), "62H30000+");
+}
+
+#[test]
+fn plus_code_length_is_made_valid() {
+    let pos = position(20.3700625, 2.7821875);
+    assert_eq!(pos.to_plus_code(5), pos.to_plus_code(6));
+    assert_eq!(pos.to_plus_code(0), "7F000000+");
+    assert_eq!(pos.to_plus_code(20).len(), 16);
+}
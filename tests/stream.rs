@@ -0,0 +1,94 @@
+#![cfg(feature = "futures")]
+
+use futures::StreamExt;
+use futures::executor::block_on;
+use futures::io::AsyncRead;
+use std::collections::VecDeque;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use ublox7::{DecodedMessage, build_ubx_frame, message_stream};
+
+enum Step {
+    Data(Vec<u8>),
+    Error(io::ErrorKind),
+    // not ready yet, wakes itself right away
+    Pending,
+}
+
+// AsyncRead replaying a script of reads, end of input once it runs out
+struct ScriptedReader(VecDeque<Step>);
+
+impl AsyncRead for ScriptedReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.0.pop_front() {
+            Some(Step::Data(data)) => {
+                buf[..data.len()].copy_from_slice(&data);
+                Poll::Ready(Ok(data.len()))
+            }
+            Some(Step::Error(kind)) => Poll::Ready(Err(io::Error::from(kind))),
+            Some(Step::Pending) => {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+            None => Poll::Ready(Ok(0)),
+        }
+    }
+}
+
+fn posllh_frame() -> Vec<u8> {
+    build_ubx_frame(0x01, 0x02, &[0; 28])
+}
+
+#[test]
+fn stream_reassembles_split_frame_across_timeout() {
+    let frame = posllh_frame();
+    let reader = ScriptedReader(VecDeque::from([
+        Step::Data(frame[..5].to_vec()),
+        Step::Error(io::ErrorKind::TimedOut),
+        Step::Pending,
+        Step::Data(frame[5..].to_vec()),
+        Step::Data(build_ubx_frame(0x05, 0x01, &[0x06, 0x08])),
+    ]));
+    let mut stream = message_stream(reader);
+
+    let messages: Vec<DecodedMessage> = block_on(stream.by_ref().collect());
+    assert_eq!(messages.len(), 2);
+    assert!(matches!(messages[0], DecodedMessage::Position(_)));
+    assert!(matches!(
+        messages[1],
+        DecodedMessage::Ack {
+            cls: 0x06,
+            id: 0x08,
+            accepted: true
+        }
+    ));
+    assert!(stream.take_error().is_none());
+}
+
+#[test]
+fn stream_ends_on_fatal_error() {
+    let reader = ScriptedReader(VecDeque::from([
+        Step::Data(posllh_frame()),
+        Step::Error(io::ErrorKind::BrokenPipe),
+        Step::Data(posllh_frame()),
+    ]));
+    let mut stream = message_stream(reader);
+
+    assert_eq!(block_on(stream.by_ref().count()), 1);
+    assert_eq!(
+        stream.take_error().unwrap().kind(),
+        io::ErrorKind::BrokenPipe
+    );
+}
+
+#[test]
+fn stream_ends_at_eof() {
+    let mut stream = message_stream(ScriptedReader(VecDeque::new()));
+    assert!(block_on(stream.next()).is_none());
+    assert!(block_on(stream.next()).is_none());
+}
@@ -0,0 +1,60 @@
+use ublox7::{ChipGeneration, SvQuality, parse_nav_svinfo};
+
+// NAV-SVINFO of a u-blox 7 tracking GPS 12 and SBAS 120, searching for GLONASS 65
+const SVINFO_PAYLOAD: [u8; 44] = [
+    0x60, 0x2B, 0x3C, 0x0F, // iTOW 255601504
+    0x03, 0x03, 0x00, 0x00, // numCh 3, globalFlags (u-blox 7), reserved
+    // chn 0, svid 12, flags (used, orbit), quality 7, cno 42, elev 65, azim 301, prRes -125
+    0x00, 0x0C, 0x0D, 0x07, 0x2A, 0x41, 0x2D, 0x01, 0x83, 0xFF, 0xFF, 0xFF,
+    // chn 1, svid 120, flags 0x10, quality 4, cno 35, elev 30, azim 205, prRes 0
+    0x01, 0x78, 0x10, 0x04, 0x23, 0x1E, 0xCD, 0x00, 0x00, 0x00, 0x00, 0x00,
+    // chn 255 (unassigned), svid 65, quality 1, cno 0, elev -91 (unknown), azim 0
+    0xFF, 0x41, 0x00, 0x01, 0x00, 0xA5, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+#[test]
+fn svinfo_parses_all_channels() {
+    let svinfo = parse_nav_svinfo(&SVINFO_PAYLOAD).unwrap();
+    assert_eq!(svinfo.i_tow, 255_601_504);
+    assert_eq!(svinfo.chip_gen, ChipGeneration::Ublox7);
+    assert_eq!(svinfo.channels.len(), 3);
+
+    let gps = &svinfo.channels[0];
+    assert_eq!((gps.chn, gps.svid, gps.flags), (0, 12, 0x0D));
+    assert_eq!(gps.quality, SvQuality::CodeCarrierLocked);
+    assert_eq!((gps.cno, gps.elevation, gps.azimuth), (42, 65, 301));
+    assert_eq!(gps.pr_res, -125);
+
+    let sbas = &svinfo.channels[1];
+    assert_eq!((sbas.svid, sbas.cno, sbas.azimuth), (120, 35, 205));
+    assert!(sbas.quality.is_tracking());
+
+    let glonass = &svinfo.channels[2];
+    assert_eq!((glonass.chn, glonass.svid), (255, 65));
+    assert_eq!(glonass.quality, SvQuality::Searching);
+    assert_eq!(glonass.elevation, -91);
+}
+
+#[test]
+fn svinfo_drops_truncated_block() {
+    let svinfo = parse_nav_svinfo(&SVINFO_PAYLOAD[..40]).unwrap();
+    assert_eq!(svinfo.channels.len(), 2);
+}
+
+#[test]
+fn svinfo_takes_num_ch_blocks_only() {
+    let mut payload = SVINFO_PAYLOAD;
+    payload[4] = 1;
+    assert_eq!(parse_nav_svinfo(&payload).unwrap().channels.len(), 1);
+}
+
+#[test]
+fn svinfo_rejects_short_header() {
+    assert!(parse_nav_svinfo(&SVINFO_PAYLOAD[..7]).is_none());
+    assert!(
+        parse_nav_svinfo(&SVINFO_PAYLOAD[..8])
+            .unwrap()
+            .channels
+            .is_empty()
+    );
+}
@@ -0,0 +1,97 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use ublox7::{GPS_WEEK_CYCLE, correct_week_rollover, gps_time_to_utc};
+
+fn unix(secs: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+// GPS week 2238 starts 2022-11-27 00:00:00 GPS
+const WEEK_2238_START: u64 = 1_669_507_200;
+
+#[test]
+fn gps_epoch_without_leap_seconds() {
+    assert_eq!(gps_time_to_utc(0, 0, 0), unix(315_964_800));
+}
+
+#[test]
+fn gps_week_and_itow_with_leap_seconds() {
+    // Wednesday 2022-11-30 00:00:00 GPS, 18 s ahead of UTC
+    let utc = gps_time_to_utc(2238, 3 * 86_400_000, 18);
+    assert_eq!(utc, unix(WEEK_2238_START + 3 * 86_400 - 18));
+
+    let gps = gps_time_to_utc(2238, 3 * 86_400_000 + 500, 0);
+    assert_eq!(
+        gps,
+        unix(WEEK_2238_START + 3 * 86_400) + Duration::from_millis(500)
+    );
+}
+
+#[test]
+fn leap_seconds_shift_the_result() {
+    // 1999-01-01 leap second era (13 s) against today's 18 s
+    let with_13 = gps_time_to_utc(2238, 0, 13);
+    let with_18 = gps_time_to_utc(2238, 0, 18);
+    assert_eq!(
+        with_13.duration_since(with_18).unwrap(),
+        Duration::from_secs(5)
+    );
+    // a negative value moves forward
+    assert_eq!(gps_time_to_utc(2238, 0, -1), unix(WEEK_2238_START + 1));
+}
+
+#[test]
+fn end_of_week_itow() {
+    let last_ms = 604_800_000 - 1;
+    let utc = gps_time_to_utc(2238, last_ms, 0);
+    assert_eq!(utc + Duration::from_millis(1), gps_time_to_utc(2239, 0, 0));
+}
+
+#[test]
+fn week_rollover_is_corrected() {
+    // old firmware resolving week 2238 into the previous 1024 week cycle
+    let rolled = gps_time_to_utc(2238 - GPS_WEEK_CYCLE as u16, 3 * 86_400_000, 18);
+    let expected = gps_time_to_utc(2238, 3 * 86_400_000, 18);
+    let reference = unix(1_577_836_800); // 2020-01-01
+
+    assert!(rolled < reference);
+    assert_eq!(correct_week_rollover(rolled, reference), expected);
+    // already after the reference: unchanged
+    assert_eq!(correct_week_rollover(expected, reference), expected);
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn naive_utc_matches_system_time() {
+    use chrono::NaiveDate;
+    use ublox7::{GPS_EPOCH_NAIVE, gps_time_to_naive_utc};
+
+    assert_eq!(gps_time_to_naive_utc(0, 0, 0), GPS_EPOCH_NAIVE);
+    assert_eq!(
+        GPS_EPOCH_NAIVE.and_utc().timestamp(),
+        ublox7::GPS_EPOCH_UNIX_SECS as i64
+    );
+
+    let expected = NaiveDate::from_ymd_opt(2022, 11, 29)
+        .unwrap()
+        .and_hms_opt(23, 59, 42)
+        .unwrap();
+    assert_eq!(gps_time_to_naive_utc(2238, 3 * 86_400_000, 18), expected);
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn naive_week_rollover_is_corrected() {
+    use chrono::NaiveDate;
+    use ublox7::{correct_week_rollover_naive, gps_time_to_naive_utc};
+
+    let rolled = gps_time_to_naive_utc(2238 - GPS_WEEK_CYCLE as u16, 3 * 86_400_000, 18);
+    let expected = gps_time_to_naive_utc(2238, 3 * 86_400_000, 18);
+    let reference = NaiveDate::from_ymd_opt(2020, 1, 1)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap();
+
+    assert!(rolled < reference);
+    assert_eq!(correct_week_rollover_naive(rolled, reference), expected);
+    assert_eq!(correct_week_rollover_naive(expected, reference), expected);
+}